@@ -0,0 +1,119 @@
+//! measures the frames per second of the terminal writer for a 160x160 camera
+//! every frame changes every pixel, so each frame is a full redraw
+//! and the frames per second of rasterizing 300 sprites into a 300x200 camera
+//! run with `cargo bench`
+use console_renderer::{camera::Camera, *};
+use std::time::Instant;
+use ABC_Game_Engine::{EntitiesAndComponents, Transform};
+
+const SIZE: usize = 160;
+const FRAMES: usize = 300;
+
+/// a gradient where every pixel changes between offsets 0 and 1
+fn gradient(offset: usize) -> Frame {
+    let mut frame = Frame::new(SIZE, SIZE, Color::default());
+    for y in 0..SIZE {
+        for x in 0..SIZE {
+            frame[y][x] = Color {
+                r: (x + offset) as u8,
+                g: y as u8,
+                b: (x + y) as u8,
+                a: 1.0,
+            };
+        }
+    }
+    frame
+}
+
+/// a 300x200 camera looking at 300 overlapping rotated sprites
+fn sprite_scene() -> EntitiesAndComponents {
+    let mut scene = EntitiesAndComponents::new();
+    let camera = scene.add_entity();
+    scene.add_component_to(camera, Camera::new(300, 200));
+    scene.add_component_to(camera, Transform::default());
+
+    for index in 0..300 {
+        let color = Color {
+            r: index as u8,
+            g: (index * 7) as u8,
+            b: (index * 13) as u8,
+            a: 1.0,
+        };
+        let sprite = if index % 2 == 0 {
+            Sprite::Circle(Circle {
+                radius: 10.0,
+                color,
+            })
+        } else {
+            Sprite::Rectangle(Rectangle {
+                width: 20.0,
+                height: 12.0,
+                color,
+            })
+        };
+        let entity = scene.add_entity();
+        scene.add_component_to(entity, sprite);
+        scene.add_component_to(
+            entity,
+            Transform {
+                x: (index * 37 % 300) as f64 - 150.0,
+                y: (index * 53 % 200) as f64 - 100.0,
+                z: index as f64,
+                rotation: index as f64 * 0.1,
+                ..Default::default()
+            },
+        );
+    }
+    scene
+}
+
+fn main() {
+    let mut renderer = Renderer::with_backend(std::io::sink());
+    renderer.set_color_depth(ColorDepth::TrueColor);
+    let scene_params = renderer.get_scene_params();
+    let frames = [gradient(0), gradient(1)];
+
+    for render_mode in [
+        RenderMode::Character,
+        RenderMode::HalfBlock,
+        RenderMode::Braille,
+        RenderMode::Quadrant,
+        RenderMode::Sextant,
+    ] {
+        renderer.set_render_mode(render_mode);
+        let start = Instant::now();
+        for frame in 0..FRAMES {
+            ascii_renderer::render_pixel_grid(&mut renderer, &frames[frame % 2], &scene_params);
+        }
+        let elapsed = start.elapsed().as_secs_f64();
+        println!(
+            "{:?} {}x{}: {:.1} fps",
+            render_mode,
+            SIZE,
+            SIZE,
+            FRAMES as f64 / elapsed
+        );
+    }
+
+    let mut scene = sprite_scene();
+    let mut renderer = Renderer::headless();
+    let mut single_threaded_fps = 0.0;
+    for render_threads in [1, 2, 4] {
+        renderer.set_render_threads(render_threads);
+        let start = Instant::now();
+        for _ in 0..FRAMES / 10 {
+            renderer.render_to_grid_ref(&mut scene);
+        }
+        let elapsed = start.elapsed().as_secs_f64();
+        let fps = (FRAMES / 10) as f64 / elapsed;
+        if render_threads == 1 {
+            single_threaded_fps = fps;
+        }
+        println!(
+            "300 sprites 300x200, {} render threads: {:.1} fps ({:.2}x)",
+            render_threads,
+            fps,
+            fps / single_threaded_fps
+        );
+    }
+}
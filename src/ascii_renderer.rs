@@ -1,73 +1,683 @@
 use crate::*;
 
-pub fn render_pixel_grid(
+/// writes the pixel grid to the renderer's backend, panics if writing fails
+pub fn render_pixel_grid(renderer: &mut Renderer, pixel_grid: &Frame, scene_params: &SceneParams) {
+    if let Err(error) = try_render_pixel_grid(renderer, pixel_grid, scene_params) {
+        panic!("{}", error);
+    }
+}
+
+/// writes the pixel grid to the renderer's backend, returns an error if writing fails
+/// the whole frame is redrawn after an error, because it is unknown how much of it reached the terminal
+pub fn try_render_pixel_grid(
     renderer: &mut Renderer,
-    pixel_grid: &Vec<Vec<Color>>,
+    pixel_grid: &Frame,
     scene_params: &SceneParams,
+) -> Result<(), RenderError> {
+    // if the pixel scale is greater than 1, scale the pixel grid into a buffer that is reused between frames
+    let pixel_scale = renderer.renderer_params.pixel_scale as usize;
+    let mut scaled_pixel_grid = std::mem::take(&mut renderer.scaled_pixel_grid);
+    let mut pixel_grid = pixel_grid;
+    if pixel_scale != 1 {
+        scale_pixel_grid(pixel_grid, pixel_scale, &mut scaled_pixel_grid);
+        pixel_grid = &scaled_pixel_grid;
+    }
+    // dithering runs on the scaled grid, where every pixel is one terminal color in the modes that are dithered
+    let mut dithered_pixel_grid = std::mem::take(&mut renderer.dithered_pixel_grid);
+    if dither_pixel_grid(
+        renderer.renderer_params.dithering,
+        renderer.renderer_params.color_depth,
+        renderer.renderer_params.render_mode,
+        pixel_grid,
+        &mut dithered_pixel_grid,
+    ) {
+        pixel_grid = &dithered_pixel_grid;
+    }
+    let result = write_pixel_grid(renderer, pixel_grid, scene_params);
+    renderer.scaled_pixel_grid = scaled_pixel_grid;
+    renderer.dithered_pixel_grid = dithered_pixel_grid;
+
+    if result.is_err() {
+        renderer.resize_terminal();
+    }
+    result.map_err(RenderError::from)
+}
+
+/// draws every pixel as a pixel_scale by pixel_scale square into the scaled pixel grid
+pub(crate) fn scale_pixel_grid(
+    pixel_grid: &Frame,
+    pixel_scale: usize,
+    scaled_pixel_grid: &mut Frame,
 ) {
-    // if the pixel scale is greater than 1, scale the pixel grid
-    // make sure that the pixel grid is not already scaled
-    if renderer.renderer_params.pixel_scale != 1
-        && pixel_grid.len() <= renderer.renderer_params.width as usize
-    {
-        let mut scaled_pixel_grid = vec![
-            vec![
-                Color::default();
-                (renderer.renderer_params.width * renderer.renderer_params.pixel_scale as u32)
-                    as usize
-            ];
-            (renderer.renderer_params.height * renderer.renderer_params.pixel_scale as u32)
-                as usize
-        ];
-
-        for (x, row) in pixel_grid.into_iter().enumerate() {
-            for (y, pixel) in row.into_iter().enumerate() {
-                for i in 0..renderer.renderer_params.pixel_scale {
-                    for j in 0..renderer.renderer_params.pixel_scale {
-                        scaled_pixel_grid
-                            [x * renderer.renderer_params.pixel_scale as usize + i as usize]
-                            [y * renderer.renderer_params.pixel_scale as usize + j as usize] =
-                            *pixel;
-                    }
-                }
+    scaled_pixel_grid.reset(
+        pixel_grid.width() * pixel_scale,
+        pixel_grid.height() * pixel_scale,
+        Color::default(),
+    );
+
+    for (y, row) in pixel_grid.rows().enumerate() {
+        for (x, pixel) in row.iter().enumerate() {
+            for i in 0..pixel_scale {
+                scaled_pixel_grid[y * pixel_scale + i][x * pixel_scale..(x + 1) * pixel_scale]
+                    .fill(*pixel);
             }
         }
-        render_pixel_grid(renderer, &scaled_pixel_grid, scene_params);
-        return;
     }
+}
+
+/// writes the cells that changed since the last frame
+fn write_pixel_grid(
+    renderer: &mut Renderer,
+    pixel_grid: &Frame,
+    scene_params: &SceneParams,
+) -> std::io::Result<()> {
+    let synchronized_output = renderer.get_synchronized_output();
+    if synchronized_output {
+        crossterm::queue!(
+            renderer.handle,
+            crossterm::terminal::BeginSynchronizedUpdate
+        )?;
+    }
+
+    // the terminal was resized, clear it and redraw the whole frame
+    if renderer.update_terminal_size() {
+        renderer.resize_terminal();
+    }
+    // the frame changed size since the last one was written, clear what the last one left behind
+    let last_pixel_grid = &renderer.last_pixel_grid;
+    if !last_pixel_grid.is_empty()
+        && (last_pixel_grid.width() != pixel_grid.width()
+            || last_pixel_grid.height() != pixel_grid.height())
+    {
+        renderer.resize_terminal();
+    }
+
+    let (cell_width, cell_height) = renderer.renderer_params.render_mode.pixels_per_cell();
+    let (cell_width, cell_height) = (cell_width as usize, cell_height as usize);
+    let (grid_width, grid_height) = (pixel_grid.width(), pixel_grid.height());
+
+    // cells that don't fit in the terminal are clipped, writing them would wrap and garble the frame
+    let (visible_columns, visible_rows) = renderer.visible_cells();
+    let cell_rows = grid_height.div_ceil(cell_height).min(visible_rows as usize);
+    let cell_columns = grid_width
+        .div_ceil(cell_width)
+        .min(visible_columns as usize);
+
+    let render_mode = renderer.renderer_params.render_mode;
+    let mut writer = SpanWriter::new(&mut renderer.handle, renderer.renderer_params.color_depth);
+    for cell_row in 0..cell_rows {
+        for cell_column in 0..cell_columns {
+            let row = cell_row * cell_height;
+            let column = cell_column * cell_width;
+            let rows = row..(row + cell_height).min(grid_height);
+            let columns = column..(column + cell_width).min(grid_width);
 
-    let mut pixel_character = "".to_string();
-    for (x, row) in pixel_grid.into_iter().enumerate() {
-        for (y, pixel) in row.into_iter().enumerate() {
-            // if the pixel is the same as the last pixel, don't render it
-            if renderer.last_pixel_grid.len() != 0 && *pixel == renderer.last_pixel_grid[x][y] {
+            // if none of the pixels in the cell changed, don't render it
+            if !cell_changed(
+                &renderer.last_pixel_grid,
+                pixel_grid,
+                rows.clone(),
+                columns.clone(),
+            ) {
                 continue;
             }
-            crossterm::queue!(renderer.handle, cursor::MoveTo(y as u16, x as u16))
-                .expect("Failed to move cursor");
 
-            // \x08 is backspace
+            let (glyph, foreground, background) =
+                cell_contents(render_mode, pixel_grid, rows, columns, scene_params);
+            writer.write_cell(
+                cell_column as u16,
+                cell_row as u16,
+                glyph,
+                foreground,
+                background,
+            )?;
+        }
+    }
+    writer.finish()?;
+    if synchronized_output {
+        crossterm::queue!(renderer.handle, crossterm::terminal::EndSynchronizedUpdate)?;
+    }
+
+    renderer.handle.flush()?;
+    renderer.last_pixel_grid.clone_from(pixel_grid);
+    Ok(())
+}
+
+/// picks the glyph, foreground and background color of the cell that covers the pixels
+/// None colors are left as the terminal's default color
+pub(crate) fn cell_contents(
+    render_mode: RenderMode,
+    pixel_grid: &Frame,
+    rows: std::ops::Range<usize>,
+    columns: std::ops::Range<usize>,
+    scene_params: &SceneParams,
+) -> (char, Option<Color>, Option<Color>) {
+    let pixel = pixel_grid[rows.start][columns.start];
+    match render_mode {
+        RenderMode::Character => {
             if pixel.a == 0.0 {
-                write!(renderer.handle, "\x08{}", " ").expect("failed to write white space");
+                (' ', None, None)
+            } else if scene_params.is_random_chars {
+                let character = char::from(rand::thread_rng().gen_range(33..126));
+                (character, Some(pixel), None)
+            } else if let Some(luminance_ramp) = &scene_params.luminance_ramp {
+                let character = luminance_ramp_character(luminance_ramp, &pixel);
+                (character, Some(pixel), None)
+            } else {
+                (scene_params.character, Some(pixel), None)
+            }
+        }
+        RenderMode::HalfBlock => {
+            // the bottom pixel is missing on the last row of an odd height grid
+            let top = Some(pixel).filter(|pixel| pixel.a != 0.0);
+            let bottom = pixel_grid
+                .get(columns.start, rows.start + 1)
+                .copied()
+                .filter(|pixel| pixel.a != 0.0);
+
+            match (top, bottom) {
+                (Some(_), _) => ('▀', top, bottom),
+                (None, Some(_)) => ('▄', bottom, None),
+                (None, None) => (' ', None, None),
+            }
+        }
+        RenderMode::Braille => {
+            let (glyph, foreground) =
+                braille_cell(pixel_grid, rows, columns, scene_params.background_color);
+            (glyph, foreground, None)
+        }
+        RenderMode::Quadrant | RenderMode::Sextant => {
+            let (pattern, foreground, background) = two_color_cell(pixel_grid, rows, columns);
+            let glyph = if render_mode == RenderMode::Quadrant {
+                QUADRANT_GLYPHS[pattern as usize]
+            } else {
+                sextant_glyph(pattern)
+            };
+            (glyph, Some(foreground), background)
+        }
+    }
+}
+
+/// returns true if any pixel covered by the cell is different from the last frame
+fn cell_changed(
+    last_pixel_grid: &Frame,
+    pixel_grid: &Frame,
+    rows: std::ops::Range<usize>,
+    columns: std::ops::Range<usize>,
+) -> bool {
+    // an empty last grid means that there is no previous frame
+    if last_pixel_grid.width() != pixel_grid.width()
+        || last_pixel_grid.height() != pixel_grid.height()
+    {
+        return true;
+    }
+    rows.into_iter()
+        .any(|row| last_pixel_grid[row][columns.clone()] != pixel_grid[row][columns.clone()])
+}
+
+/// the bit of each braille dot, indexed by [row][column] inside of the cell
+const BRAILLE_DOTS: [[u32; 2]; 4] = [[0x01, 0x08], [0x02, 0x10], [0x04, 0x20], [0x40, 0x80]];
+
+/// picks the braille character for a 2x4 cell and the average color of the dots that are set
+/// a dot is set if its pixel is visible and not the background color
+fn braille_cell(
+    pixel_grid: &Frame,
+    rows: std::ops::Range<usize>,
+    columns: std::ops::Range<usize>,
+    background_color: Color,
+) -> (char, Option<Color>) {
+    let mut dots = 0;
+    let mut set_pixels = [Color::default(); 8];
+    let mut set_pixel_count = 0;
+    for (dot_row, row) in rows.enumerate() {
+        for (dot_column, column) in columns.clone().enumerate() {
+            let pixel = pixel_grid[row][column];
+            if pixel.a != 0.0 && pixel != background_color {
+                dots |= BRAILLE_DOTS[dot_row][dot_column];
+                set_pixels[set_pixel_count] = pixel;
+                set_pixel_count += 1;
+            }
+        }
+    }
+
+    match average_color(&set_pixels[..set_pixel_count]) {
+        Some(color) => (
+            char::from_u32(0x2800 + dots).expect("braille characters are always valid"),
+            Some(color),
+        ),
+        None => (' ', None),
+    }
+}
+
+/// quadrant characters indexed by their pattern, the bit of a pixel is 1 << (row * 2 + column)
+const QUADRANT_GLYPHS: [char; 16] = [
+    ' ', '▘', '▝', '▀', '▖', '▌', '▞', '▛', '▗', '▚', '▐', '▜', '▄', '▙', '▟', '█',
+];
+
+/// the sextant character for a pattern, the bit of a pixel is 1 << (row * 2 + column)
+fn sextant_glyph(pattern: u32) -> char {
+    // the sextant block skips the patterns that already exist as other block characters
+    match pattern {
+        0 => ' ',
+        0b010101 => '▌',
+        0b101010 => '▐',
+        0b111111 => '█',
+        _ => {
+            let skipped = (pattern > 0b010101) as u32 + (pattern > 0b101010) as u32;
+            char::from_u32(0x1FB00 + pattern - 1 - skipped)
+                .expect("sextant characters are always valid")
+        }
+    }
+}
+
+/// splits the pixels of a cell into the two colors that represent it best
+/// returns the pattern of the pixels that use the foreground color, with the bit 1 << (row * 2 + column),
+/// the foreground color and the background color, the background is None if every pixel is in the foreground
+fn two_color_cell(
+    pixel_grid: &Frame,
+    rows: std::ops::Range<usize>,
+    columns: std::ops::Range<usize>,
+) -> (u32, Color, Option<Color>) {
+    let mut pixel_buffer = [(0, Color::default()); 6];
+    let mut pixel_count = 0;
+    for (cell_row, row) in rows.enumerate() {
+        for (cell_column, column) in columns.clone().enumerate() {
+            pixel_buffer[pixel_count] =
+                (1 << (cell_row * 2 + cell_column), pixel_grid[row][column]);
+            pixel_count += 1;
+        }
+    }
+    let pixels = &pixel_buffer[..pixel_count];
+    let all_bits = pixels.iter().fold(0, |bits, (bit, _)| bits | bit);
+    let first_bit = pixels[0].0;
+
+    let mut best_split = (all_bits, Color::default(), None);
+    let mut best_error = f32::MAX;
+    let mut foreground = [Color::default(); 6];
+    let mut background = [Color::default(); 6];
+    // the first pixel is always kept in the foreground,
+    // the other half of the splits are the same splits with the colors swapped
+    for pattern in (0..=all_bits).filter(|pattern| pattern & first_bit != 0) {
+        if pattern & !all_bits != 0 {
+            continue;
+        }
+        let (mut foreground_count, mut background_count) = (0, 0);
+        for (bit, pixel) in pixels {
+            if pattern & bit != 0 {
+                foreground[foreground_count] = *pixel;
+                foreground_count += 1;
             } else {
-                if scene_params.is_random_chars {
-                    pixel_character +=
-                        &char::from(rand::thread_rng().gen_range(33..126)).to_string();
-                } else {
-                    pixel_character += &scene_params.character.to_string();
-                }
-
-                write!(
-                    renderer.handle,
-                    "\x08{}",
-                    pixel_character.truecolor(pixel.r, pixel.g, pixel.b)
-                )
-                .expect("failed to write pixel");
-                pixel_character.clear();
+                background[background_count] = *pixel;
+                background_count += 1;
             }
         }
+        let foreground = &foreground[..foreground_count];
+        let background = &background[..background_count];
+
+        let foreground_color =
+            average_color(foreground).expect("the foreground always has a pixel");
+        let background_color = average_color(background);
+        let error = color_error(foreground, foreground_color)
+            + background_color.map_or(0.0, |color| color_error(background, color));
+        if error < best_error {
+            best_error = error;
+            best_split = (pattern, foreground_color, background_color);
+        }
+    }
+    best_split
+}
+
+/// the sum of the squared distances between the pixels and their average color
+fn color_error(pixels: &[Color], average: Color) -> f32 {
+    pixels
+        .iter()
+        .map(|pixel| {
+            (pixel.r as f32 - average.r as f32).powi(2)
+                + (pixel.g as f32 - average.g as f32).powi(2)
+                + (pixel.b as f32 - average.b as f32).powi(2)
+        })
+        .sum()
+}
+
+/// the average color of the pixels, None if there are no pixels
+/// takes a slice, so the cells can gather their pixels in fixed size buffers instead of allocating
+fn average_color(pixels: &[Color]) -> Option<Color> {
+    if pixels.is_empty() {
+        return None;
+    }
+    let (mut r, mut g, mut b, mut a) = (0u32, 0u32, 0u32, 0.0);
+    for pixel in pixels {
+        r += pixel.r as u32;
+        g += pixel.g as u32;
+        b += pixel.b as u32;
+        a += pixel.a;
+    }
+    let count = pixels.len() as u32;
+    Some(Color {
+        r: (r / count) as u8,
+        g: (g / count) as u8,
+        b: (b / count) as u8,
+        a: a / count as f32,
+    })
+}
+
+/// the character of the luminance ramp that matches the brightness of the pixel
+fn luminance_ramp_character(luminance_ramp: &str, pixel: &Color) -> char {
+    let luminance = 0.2126 * pixel.r as f32 + 0.7152 * pixel.g as f32 + 0.0722 * pixel.b as f32;
+    let last_index = luminance_ramp.chars().count() - 1;
+    let index = (luminance / 255.0 * last_index as f32).round() as usize;
+    luminance_ramp
+        .chars()
+        .nth(index.min(last_index))
+        .expect("the luminance ramp is never empty")
+}
+
+/// writes cells to the terminal, merging runs of changed cells into spans
+/// the cursor is only moved when it isn't already in place, and colors are only set when they change
+struct SpanWriter<'a> {
+    handle: &'a mut std::io::BufWriter<BackendWriter>,
+    color_depth: ColorDepth,
+    // None if the position of the cursor is unknown
+    cursor: Option<(u16, u16)>,
+    // the colors that are currently set on the terminal, None is the terminal's default color
+    foreground: Option<Color>,
+    background: Option<Color>,
+}
+
+impl<'a> SpanWriter<'a> {
+    fn new(handle: &'a mut std::io::BufWriter<BackendWriter>, color_depth: ColorDepth) -> Self {
+        SpanWriter {
+            handle,
+            color_depth,
+            cursor: None,
+            foreground: None,
+            background: None,
+        }
+    }
+
+    /// writes a glyph at the cell with an optional foreground and background color
+    fn write_cell(
+        &mut self,
+        column: u16,
+        row: u16,
+        glyph: char,
+        foreground: Option<Color>,
+        background: Option<Color>,
+    ) -> std::io::Result<()> {
+        if self.cursor != Some((column, row)) {
+            crossterm::queue!(self.handle, cursor::MoveTo(column, row))?;
+        }
+
+        // compare the quantized colors, two colors that end up as the same palette color don't need a new escape
+        let color_depth = self.color_depth;
+        let foreground = foreground
+            .filter(|_| color_depth != ColorDepth::Monochrome)
+            .map(|color| color_depth.quantize(color));
+        let background = background
+            .filter(|_| color_depth != ColorDepth::Monochrome)
+            .map(|color| color_depth.quantize(color));
+
+        let foreground_changed = foreground != self.foreground;
+        let background_changed = background != self.background;
+        if foreground_changed || background_changed {
+            self.write_colors(
+                foreground_changed.then_some(foreground),
+                background_changed.then_some(background),
+            )?;
+        }
+        self.foreground = foreground;
+        self.background = background;
+
+        let mut glyph_buffer = [0; 4];
+        self.handle
+            .write_all(glyph.encode_utf8(&mut glyph_buffer).as_bytes())?;
+        self.cursor = Some((column + 1, row));
+        Ok(())
+    }
+
+    /// writes a single SGR escape that sets the colors that changed, Some(None) goes back to the default color
+    fn write_colors(
+        &mut self,
+        foreground: Option<Option<Color>>,
+        background: Option<Option<Color>>,
+    ) -> std::io::Result<()> {
+        self.handle.write_all(b"\x1b[")?;
+        match foreground {
+            Some(Some(color)) => self.color_depth.write_foreground(self.handle, color)?,
+            Some(None) => self.handle.write_all(b"39")?,
+            None => (),
+        }
+        if foreground.is_some() && background.is_some() {
+            self.handle.write_all(b";")?;
+        }
+        match background {
+            Some(Some(color)) => self.color_depth.write_background(self.handle, color)?,
+            Some(None) => self.handle.write_all(b"49")?,
+            None => (),
+        }
+        self.handle.write_all(b"m")
+    }
+
+    /// resets the colors so that nothing else written to the terminal is colored
+    fn finish(self) -> std::io::Result<()> {
+        if self.foreground.is_some() || self.background.is_some() {
+            write!(self.handle, "\x1b[0m")?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod ascii_renderer_tests {
+    #[test]
+    fn half_block_packs_two_rows_per_cell() {
+        use crate::{test_helpers::*, *};
+
+        let (mut renderer, backend) = memory_renderer();
+        renderer.set_render_mode(RenderMode::HalfBlock);
+        backend.take();
+
+        let pixel_grid = FrameBuffer::from(vec![vec![RED], vec![BLUE], vec![RED], vec![BLUE]]);
+        let scene_params = renderer.get_scene_params();
+
+        ascii_renderer::render_pixel_grid(&mut renderer, &pixel_grid, &scene_params);
+        let frame = String::from_utf8(backend.take()).unwrap();
+        assert!(frame.contains("\x1b[38;2;255;0;0;48;2;0;0;255m▀"));
+        assert_eq!(frame.matches('▀').count(), 2);
+
+        // changing one pixel only redraws the cell that covers it
+        let mut changed_grid = pixel_grid.clone();
+        changed_grid[3][0] = RED;
+        ascii_renderer::render_pixel_grid(&mut renderer, &changed_grid, &scene_params);
+        let frame = String::from_utf8(backend.take()).unwrap();
+        assert_eq!(frame.matches('▀').count(), 1);
+    }
+
+    #[test]
+    fn braille_sets_dots_that_are_not_background() {
+        use crate::{test_helpers::*, *};
+
+        let (mut renderer, backend) = memory_renderer();
+        renderer.set_render_mode(RenderMode::Braille);
+        backend.take();
+
+        let scene_params = renderer.get_scene_params();
+        // a diagonal line through a single 2x4 cell
+        let mut pixel_grid = FrameBuffer::from(vec![vec![scene_params.background_color; 2]; 4]);
+        pixel_grid[0][0] = GREEN;
+        pixel_grid[1][1] = GREEN;
+        pixel_grid[3][1] = GREEN;
+
+        ascii_renderer::render_pixel_grid(&mut renderer, &pixel_grid, &scene_params);
+        let frame = String::from_utf8(backend.take()).unwrap();
+        assert!(frame.contains("\x1b[38;2;0;255;0m\u{2891}"));
+    }
+
+    #[test]
+    fn quadrant_and_sextant_fit_two_colors() {
+        use crate::{test_helpers::*, *};
+
+        let (mut renderer, backend) = memory_renderer();
+        renderer.set_render_mode(RenderMode::Quadrant);
+        backend.take();
+
+        let scene_params = renderer.get_scene_params();
+        let pixel_grid = FrameBuffer::from(vec![vec![RED, BLUE], vec![RED, BLUE]]);
+        ascii_renderer::render_pixel_grid(&mut renderer, &pixel_grid, &scene_params);
+        let frame = String::from_utf8(backend.take()).unwrap();
+        assert!(frame.contains("\x1b[38;2;255;0;0;48;2;0;0;255m▌"));
+
+        renderer.set_render_mode(RenderMode::Sextant);
+        backend.take();
+        let pixel_grid =
+            FrameBuffer::from(vec![vec![RED, RED], vec![BLUE, BLUE], vec![BLUE, BLUE]]);
+        ascii_renderer::render_pixel_grid(&mut renderer, &pixel_grid, &scene_params);
+        let frame = String::from_utf8(backend.take()).unwrap();
+        assert!(frame.contains("\x1b[38;2;255;0;0;48;2;0;0;255m\u{1FB02}"));
+    }
+
+    #[test]
+    fn runs_of_changed_cells_are_written_as_spans() {
+        use crate::{test_helpers::*, *};
+
+        let (mut renderer, backend) = memory_renderer();
+
+        let pixel_grid = FrameBuffer::from(vec![vec![RED, RED, BLUE, BLUE]; 2]);
+        let scene_params = renderer.get_scene_params();
+        ascii_renderer::render_pixel_grid(&mut renderer, &pixel_grid, &scene_params);
+        let frame = String::from_utf8(backend.take()).unwrap();
+        assert_eq!(
+            frame,
+            "\x1b[1;1H\x1b[38;2;255;0;0m==\x1b[38;2;0;0;255m==\
+             \x1b[2;1H\x1b[38;2;255;0;0m==\x1b[38;2;0;0;255m==\x1b[0m"
+        );
+
+        // only the changed cell is written, the cursor has to move to it
+        let mut changed_grid = pixel_grid.clone();
+        changed_grid[1][2] = RED;
+        ascii_renderer::render_pixel_grid(&mut renderer, &changed_grid, &scene_params);
+        let frame = String::from_utf8(backend.take()).unwrap();
+        assert_eq!(frame, "\x1b[2;3H\x1b[38;2;255;0;0m=\x1b[0m");
+    }
+
+    #[test]
+    fn monochrome_luminance_ramp_has_no_color() {
+        use crate::{test_helpers::*, *};
+
+        let backend = MemoryBackend::new();
+        let mut renderer = Renderer::with_backend(backend.clone());
+        renderer.set_color_depth(ColorDepth::Monochrome);
+        renderer
+            .set_scene_params(SceneParams::new().with_luminance_ramp(Some(DEFAULT_LUMINANCE_RAMP)));
+        backend.take();
+
+        let gray = Color {
+            r: 128,
+            g: 128,
+            b: 128,
+            a: 1.0,
+        };
+        let pixel_grid = FrameBuffer::from(vec![vec![WHITE, gray, Color::default()]]);
+        let scene_params = renderer.get_scene_params();
+        ascii_renderer::render_pixel_grid(&mut renderer, &pixel_grid, &scene_params);
+        let frame = String::from_utf8(backend.take()).unwrap();
+
+        assert!(frame.ends_with("@+ "));
+        assert!(!frame.contains("38;2"));
+
+        // a ramp that was built at runtime, for example read from a config file
+        let ramp = String::from(" o0");
+        let mut renderer = Renderer::with_backend(backend.clone());
+        renderer.set_color_depth(ColorDepth::Monochrome);
+        renderer.set_scene_params(SceneParams::new().with_luminance_ramp(Some(ramp)));
+        let scene_params = renderer.get_scene_params();
+        ascii_renderer::render_pixel_grid(&mut renderer, &pixel_grid, &scene_params);
+        let frame = String::from_utf8(backend.take()).unwrap();
+        assert!(frame.ends_with("0o "));
+    }
+
+    #[test]
+    fn frame_is_clipped_to_the_terminal_size() {
+        use crate::{test_helpers::*, *};
+
+        let backend = MemoryBackend::new();
+        backend.set_terminal_size(Some((3, 2)));
+        let mut renderer = Renderer::with_backend(backend.clone());
+        renderer.set_color_depth(ColorDepth::TrueColor);
+        backend.take();
+
+        let pixel_grid = FrameBuffer::new(4, 4, RED);
+        let scene_params = renderer.get_scene_params();
+        ascii_renderer::render_pixel_grid(&mut renderer, &pixel_grid, &scene_params);
+        let frame = String::from_utf8(backend.take()).unwrap();
+        assert_eq!(frame.matches('=').count(), 6);
+        assert!(!frame.contains("\x1b[8;"));
+
+        // growing the terminal clears it and redraws the whole frame
+        backend.set_terminal_size(Some((10, 10)));
+        ascii_renderer::render_pixel_grid(&mut renderer, &pixel_grid, &scene_params);
+        assert_eq!(renderer.get_terminal_size(), Some((10, 10)));
+        let frame = String::from_utf8(backend.take()).unwrap();
+        assert!(frame.contains("\x1b[2J"));
+        assert_eq!(frame.matches('=').count(), 16);
+    }
+
+    #[test]
+    fn a_frame_of_a_different_size_clears_the_screen() {
+        use crate::{test_helpers::*, *};
+
+        let (mut renderer, backend) = memory_renderer();
+        let scene_params = renderer.get_scene_params();
+        for (size, clears) in [(4, false), (2, true), (2, false)] {
+            let pixel_grid = FrameBuffer::new(size, size, RED);
+            ascii_renderer::render_pixel_grid(&mut renderer, &pixel_grid, &scene_params);
+            let frame = String::from_utf8(backend.take()).unwrap();
+            assert_eq!(frame.contains("\x1b[2J"), clears, "{}", size);
+        }
+    }
+
+    #[test]
+    fn synchronized_output_wraps_the_frame() {
+        use crate::{test_helpers::*, *};
+
+        let (mut renderer, backend) = memory_renderer();
+        assert!(!renderer.get_synchronized_output());
+        renderer.set_synchronized_output(true);
+
+        let pixel_grid = FrameBuffer::new(2, 1, Color::default());
+        let scene_params = renderer.get_scene_params();
+        ascii_renderer::render_pixel_grid(&mut renderer, &pixel_grid, &scene_params);
+        let frame = String::from_utf8(backend.take()).unwrap();
+        assert!(frame.starts_with("\x1b[?2026h"));
+        assert!(frame.ends_with("\x1b[?2026l"));
     }
 
-    renderer.handle.flush().expect("failed to flush stdout");
-    renderer.last_pixel_grid = pixel_grid.clone();
+    #[test]
+    fn switching_the_backend_keeps_the_synchronized_output_choice() {
+        use crate::*;
+
+        struct SynchronizedBackend;
+        impl std::io::Write for SynchronizedBackend {
+            fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+                Ok(buf.len())
+            }
+            fn flush(&mut self) -> std::io::Result<()> {
+                Ok(())
+            }
+        }
+        impl RenderBackend for SynchronizedBackend {
+            fn supports_synchronized_output(&self) -> bool {
+                true
+            }
+        }
+
+        let mut renderer = Renderer::with_backend(SynchronizedBackend);
+        assert!(renderer.get_synchronized_output());
+        renderer.set_backend(MemoryBackend::new());
+        assert!(!renderer.get_synchronized_output());
+
+        renderer.set_synchronized_output(false);
+        renderer.set_backend(SynchronizedBackend);
+        assert!(!renderer.get_synchronized_output());
+    }
 }
@@ -1,13 +1,33 @@
 use crate::*;
 
+/// The part of the screen a camera is drawn to, in terminal cells
+/// cells are converted to pixels with the render mode and pixel scale of the renderer
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct Viewport {
+    /// the column of the left edge
+    pub x: u32,
+    /// the row of the top edge
+    pub y: u32,
+    /// the number of columns
+    pub width: u32,
+    /// the number of rows
+    pub height: u32,
+}
+
 /// A simple camera that can be used to move the view around
 #[derive(Clone)]
 pub struct Camera {
     pub(crate) width: u32,
     pub(crate) height: u32,
     // if false, the camera will not be used by the renderer
-    // if multiple cameras are active, the program will pick the first one
+    // if multiple active cameras don't have a viewport, the program will pick the first one
     pub(crate) is_active: bool,
+    // None fills the top left of the screen with the size of the camera
+    pub(crate) viewport: Option<Viewport>,
+    // the entity whose Image sprite the camera is drawn to, None draws it to the screen
+    pub(crate) render_target: Option<Entity>,
+    // cameras that draw to a texture are drawn lowest render order first
+    pub(crate) render_order: i32,
 }
 
 impl Camera {
@@ -17,6 +37,9 @@ impl Camera {
             width,
             height,
             is_active: true,
+            viewport: None,
+            render_target: None,
+            render_order: 0,
         }
     }
 
@@ -26,6 +49,9 @@ impl Camera {
             width: 160,
             height: 160,
             is_active: true,
+            viewport: None,
+            render_target: None,
+            render_order: 0,
         }
     }
 
@@ -38,6 +64,42 @@ impl Camera {
     pub fn set_active(&mut self, is_active: bool) {
         self.is_active = is_active;
     }
+
+    /// draws the camera to a region of the screen, the size of the viewport replaces the size of the camera
+    /// every active camera with a viewport is drawn, in order, on top of the first active camera without one,
+    /// clipped to the size of that camera
+    /// for split screen and picture in picture views
+    pub fn set_viewport(&mut self, viewport: Option<Viewport>) {
+        self.viewport = viewport;
+    }
+
+    pub fn get_viewport(&self) -> Option<Viewport> {
+        self.viewport
+    }
+
+    /// draws the camera to the Image sprite of the target entity instead of the screen,
+    /// for mirrors, minimaps and monitors, an Image sprite is added if the target has no sprite
+    /// the texture has one pixel for every unit the camera sees, so it is the size of the camera
+    /// cameras that draw to a texture are drawn before the screen, see set_render_order
+    /// the camera is skipped if the target entity was removed or has a sprite that isn't an Image
+    pub fn set_render_target(&mut self, render_target: Option<Entity>) {
+        self.render_target = render_target;
+    }
+
+    pub fn get_render_target(&self) -> Option<Entity> {
+        self.render_target
+    }
+
+    /// cameras that draw to a texture are drawn lowest render order first, cameras with the same order in any order
+    /// a camera sees the textures of cameras with a lower order as they are this frame,
+    /// and the other textures as they were last frame
+    pub fn set_render_order(&mut self, render_order: i32) {
+        self.render_order = render_order;
+    }
+
+    pub fn get_render_order(&self) -> i32 {
+        self.render_order
+    }
 }
 
 // TODO: benchmark this, it should be faster than just rendering everything,
@@ -60,8 +122,10 @@ pub fn object_is_in_view_of_camera(
             square_is_in_view_of_camera(camera, object_transform, diameter, diameter)
         }
         Sprite::Image(Image { texture }) => {
-            let (texture_width, texture_height) =
-                (texture.pixels[0].len() as f64, texture.pixels.len() as f64);
+            let (texture_width, texture_height) = (
+                texture.pixels.width() as f64,
+                texture.pixels.height() as f64,
+            );
             let max_dist = (texture_width.powi(2) + texture_height.powi(2)).sqrt();
             square_is_in_view_of_camera(camera, object_transform, max_dist as f64, max_dist as f64)
         }
@@ -72,8 +136,8 @@ pub fn object_is_in_view_of_camera(
         }) => {
             let current_texture = &frames[*current_frame].texture.pixels;
             let (width, height) = (
-                current_texture[0].len() as f64,
-                current_texture.len() as f64,
+                current_texture.width() as f64,
+                current_texture.height() as f64,
             );
             square_is_in_view_of_camera(camera, object_transform, width, height)
         }
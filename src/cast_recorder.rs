@@ -0,0 +1,134 @@
+use std::io::{self, Write};
+use std::time::{Instant, SystemTime, UNIX_EPOCH};
+
+/// Records the output of the renderer as an asciicast v2 file that can be played with asciinema
+/// every presented frame becomes one output event, timed from when it was flushed to the backend
+/// only output events are written, a terminal resize during the recording doesn't become a resize ("r") event
+pub(crate) struct CastRecorder {
+    output: Box<dyn Write>,
+    start: Instant,
+    // the bytes of the frame that is currently being written
+    pending: Vec<u8>,
+    // the first error writing the recording, the rest of the recording is dropped after it
+    error: Option<io::Error>,
+}
+
+impl CastRecorder {
+    /// writes the header of the recording for a terminal of the given size (columns, rows)
+    /// the TERM of the environment is recorded if it is set
+    pub(crate) fn new(mut output: Box<dyn Write>, (columns, rows): (u16, u16)) -> io::Result<Self> {
+        let timestamp = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map_or(0, |duration| duration.as_secs());
+        write!(
+            output,
+            "{{\"version\": 2, \"width\": {}, \"height\": {}, \"timestamp\": {}",
+            columns, rows, timestamp
+        )?;
+        if let Ok(term) = std::env::var("TERM") {
+            output.write_all(b", \"env\": {\"TERM\": \"")?;
+            write_escaped(&mut output, &term)?;
+            output.write_all(b"\"}")?;
+        }
+        output.write_all(b"}\n")?;
+        Ok(CastRecorder {
+            output,
+            start: Instant::now(),
+            pending: vec![],
+            error: None,
+        })
+    }
+
+    /// collects output until the frame is presented
+    pub(crate) fn record(&mut self, bytes: &[u8]) {
+        self.pending.extend_from_slice(bytes);
+    }
+
+    /// writes everything since the last presented frame as one output event
+    pub(crate) fn present(&mut self) {
+        if self.pending.is_empty() || self.error.is_some() {
+            self.pending.clear();
+            return;
+        }
+        let time = self.start.elapsed().as_secs_f64();
+        let result = write_event(&mut self.output, time, &self.pending);
+        self.pending.clear();
+        if let Err(error) = result {
+            self.error = Some(error);
+        }
+    }
+
+    /// presents the last frame and flushes the recording, returns the first error that happened while recording
+    pub(crate) fn finish(mut self) -> io::Result<()> {
+        self.present();
+        match self.error.take() {
+            Some(error) => Err(error),
+            None => self.output.flush(),
+        }
+    }
+}
+
+/// writes an output event line, [time, "o", "data"]
+fn write_event(output: &mut dyn Write, time: f64, bytes: &[u8]) -> io::Result<()> {
+    write!(output, "[{:.6}, \"o\", \"", time)?;
+    // frames are always flushed whole, so they are valid utf8 unless the backend cut a glyph in half
+    write_escaped(output, &String::from_utf8_lossy(bytes))?;
+    output.write_all(b"\"]\n")
+}
+
+/// writes the text escaped for the inside of a json string
+fn write_escaped(output: &mut dyn Write, text: &str) -> io::Result<()> {
+    for character in text.chars() {
+        match character {
+            '"' => output.write_all(b"\\\"")?,
+            '\\' => output.write_all(b"\\\\")?,
+            '\n' => output.write_all(b"\\n")?,
+            '\r' => output.write_all(b"\\r")?,
+            character if (character as u32) < 0x20 || character == '\u{7f}' => {
+                write!(output, "\\u{:04x}", character as u32)?
+            }
+            character => {
+                let mut buffer = [0; 4];
+                output.write_all(character.encode_utf8(&mut buffer).as_bytes())?
+            }
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod cast_recorder_tests {
+    #[test]
+    fn frames_are_recorded_as_output_events() {
+        use crate::{test_helpers::*, *};
+
+        let backend = MemoryBackend::new();
+        backend.set_terminal_size(Some((4, 2)));
+        let mut renderer = Renderer::with_backend(backend.clone());
+        renderer.set_color_depth(ColorDepth::TrueColor);
+
+        let recording = MemoryBackend::new();
+        renderer.start_recording(recording.clone()).unwrap();
+        assert!(renderer.is_recording());
+
+        let pixel_grid = FrameBuffer::new(4, 2, RED);
+        let scene_params = renderer.get_scene_params();
+        ascii_renderer::render_pixel_grid(&mut renderer, &pixel_grid, &scene_params);
+        renderer.stop_recording().unwrap();
+        assert!(!renderer.is_recording());
+
+        let recording = String::from_utf8(recording.take()).unwrap();
+        let lines = recording.lines().collect::<Vec<_>>();
+        assert_eq!(lines.len(), 2);
+        assert!(lines[0].starts_with("{\"version\": 2, \"width\": 4, \"height\": 2,"));
+        match std::env::var("TERM") {
+            Ok(term) => {
+                assert!(lines[0].ends_with(&format!("\"env\": {{\"TERM\": \"{}\"}}}}", term)))
+            }
+            Err(_) => assert!(!lines[0].contains("\"env\"")),
+        }
+        assert!(lines[1].starts_with('['));
+        assert!(lines[1].contains(", \"o\", \"\\u001b[?25l\\u001b[0m\\u001b[2J"));
+        assert!(lines[1].ends_with("\\u001b[38;2;255;0;0m====\\u001b[2;1H====\\u001b[0m\"]"));
+    }
+}
@@ -0,0 +1,204 @@
+use crate::Color;
+use std::io::{self, Write};
+
+/// ColorDepth decides how colors are written to the terminal
+/// colors are quantized to the closest color of the palette if the terminal can't show 24 bit color
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum ColorDepth {
+    /// 24 bit color
+    #[default]
+    TrueColor,
+    /// the xterm 256 color palette
+    Ansi256,
+    /// the 16 standard ansi colors, works on the linux console and most old terminals
+    Ansi16,
+    /// no color escapes at all, works best with a luminance ramp or the braille render mode
+    Monochrome,
+}
+
+/// the usual xterm values of the 16 ansi colors
+const ANSI16_PALETTE: [(u8, u8, u8); 16] = [
+    (0, 0, 0),
+    (205, 0, 0),
+    (0, 205, 0),
+    (205, 205, 0),
+    (0, 0, 238),
+    (205, 0, 205),
+    (0, 205, 205),
+    (229, 229, 229),
+    (127, 127, 127),
+    (255, 0, 0),
+    (0, 255, 0),
+    (255, 255, 0),
+    (92, 92, 255),
+    (255, 0, 255),
+    (0, 255, 255),
+    (255, 255, 255),
+];
+
+/// the levels of each channel in the 6x6x6 color cube of the 256 color palette
+pub(crate) const ANSI256_CUBE_LEVELS: [u8; 6] = [0, 95, 135, 175, 215, 255];
+
+impl ColorDepth {
+    /// picks the color depth from the COLORTERM, TERM and NO_COLOR environment variables
+    pub fn from_env() -> ColorDepth {
+        let var = |name| std::env::var(name).unwrap_or_default();
+        let (colorterm, term) = (var("COLORTERM"), var("TERM"));
+
+        if !var("NO_COLOR").is_empty() || term == "dumb" {
+            ColorDepth::Monochrome
+        } else if colorterm == "truecolor" || colorterm == "24bit" {
+            ColorDepth::TrueColor
+        } else if term.contains("256color") {
+            ColorDepth::Ansi256
+        } else if term.is_empty() && cfg!(windows) {
+            // windows terminals don't set TERM, but they all support 24 bit color
+            ColorDepth::TrueColor
+        } else {
+            ColorDepth::Ansi16
+        }
+    }
+
+    /// returns the closest color that can be shown with this color depth
+    /// true color and monochrome return the color unchanged
+    pub fn quantize(&self, color: Color) -> Color {
+        let (r, g, b) = match self {
+            ColorDepth::TrueColor | ColorDepth::Monochrome => return color,
+            ColorDepth::Ansi256 => ansi256_rgb(ansi256_index(color)),
+            ColorDepth::Ansi16 => ANSI16_PALETTE[ansi16_index(color) as usize],
+        };
+        Color {
+            r,
+            g,
+            b,
+            a: color.a,
+        }
+    }
+
+    /// writes the SGR parameters that set the foreground to the color, nothing if monochrome
+    pub(crate) fn write_foreground(&self, out: &mut impl Write, color: Color) -> io::Result<()> {
+        match self {
+            ColorDepth::TrueColor => write!(out, "38;2;{};{};{}", color.r, color.g, color.b),
+            ColorDepth::Ansi256 => write!(out, "38;5;{}", ansi256_index(color)),
+            ColorDepth::Ansi16 => {
+                let index = ansi16_index(color);
+                write!(
+                    out,
+                    "{}",
+                    if index < 8 {
+                        30 + index
+                    } else {
+                        90 + index - 8
+                    }
+                )
+            }
+            ColorDepth::Monochrome => Ok(()),
+        }
+    }
+
+    /// writes the SGR parameters that set the background to the color, nothing if monochrome
+    pub(crate) fn write_background(&self, out: &mut impl Write, color: Color) -> io::Result<()> {
+        match self {
+            ColorDepth::TrueColor => write!(out, "48;2;{};{};{}", color.r, color.g, color.b),
+            ColorDepth::Ansi256 => write!(out, "48;5;{}", ansi256_index(color)),
+            ColorDepth::Ansi16 => {
+                let index = ansi16_index(color);
+                write!(
+                    out,
+                    "{}",
+                    if index < 8 {
+                        40 + index
+                    } else {
+                        100 + index - 8
+                    }
+                )
+            }
+            ColorDepth::Monochrome => Ok(()),
+        }
+    }
+}
+
+fn squared_distance(color: Color, (r, g, b): (u8, u8, u8)) -> u32 {
+    (color.r as i32 - r as i32).pow(2) as u32
+        + (color.g as i32 - g as i32).pow(2) as u32
+        + (color.b as i32 - b as i32).pow(2) as u32
+}
+
+/// the index of the closest of the 16 ansi colors
+fn ansi16_index(color: Color) -> u8 {
+    (0..16)
+        .min_by_key(|index| squared_distance(color, ANSI16_PALETTE[*index as usize]))
+        .expect("the palette is never empty")
+}
+
+/// the index of the closest color in the 256 color palette, only the color cube and the gray ramp are used
+/// because the first 16 colors are different on every terminal
+fn ansi256_index(color: Color) -> u8 {
+    let closest_level = |channel: u8| {
+        (0..6)
+            .min_by_key(|level| (ANSI256_CUBE_LEVELS[*level] as i32 - channel as i32).abs())
+            .expect("there are always 6 levels")
+    };
+    let cube_index =
+        16 + 36 * closest_level(color.r) + 6 * closest_level(color.g) + closest_level(color.b);
+
+    let average = (color.r as u32 + color.g as u32 + color.b as u32) / 3;
+    let gray_index = 232 + (average.saturating_sub(3) / 10).min(23) as usize;
+
+    if squared_distance(color, ansi256_rgb(gray_index as u8))
+        < squared_distance(color, ansi256_rgb(cube_index as u8))
+    {
+        gray_index as u8
+    } else {
+        cube_index as u8
+    }
+}
+
+/// the color of an index in the 256 color palette
+fn ansi256_rgb(index: u8) -> (u8, u8, u8) {
+    match index {
+        0..=15 => ANSI16_PALETTE[index as usize],
+        16..=231 => {
+            let cube_index = (index - 16) as usize;
+            (
+                ANSI256_CUBE_LEVELS[cube_index / 36],
+                ANSI256_CUBE_LEVELS[(cube_index / 6) % 6],
+                ANSI256_CUBE_LEVELS[cube_index % 6],
+            )
+        }
+        _ => {
+            let level = 8 + 10 * (index - 232);
+            (level, level, level)
+        }
+    }
+}
+
+#[cfg(test)]
+mod color_depth_tests {
+    #[test]
+    fn quantize_picks_the_closest_palette_color() {
+        use crate::*;
+
+        let orange = Color {
+            r: 250,
+            g: 130,
+            b: 10,
+            a: 1.0,
+        };
+        let quantized = ColorDepth::Ansi256.quantize(orange);
+        assert_eq!((quantized.r, quantized.g, quantized.b), (255, 135, 0));
+
+        let gray = Color {
+            r: 100,
+            g: 101,
+            b: 99,
+            a: 1.0,
+        };
+        let quantized = ColorDepth::Ansi256.quantize(gray);
+        assert_eq!((quantized.r, quantized.g, quantized.b), (98, 98, 98));
+
+        let quantized = ColorDepth::Ansi16.quantize(orange);
+        assert_eq!((quantized.r, quantized.g, quantized.b), (205, 205, 0));
+        assert_eq!(ColorDepth::TrueColor.quantize(orange), orange);
+    }
+}
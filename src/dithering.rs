@@ -0,0 +1,256 @@
+use crate::*;
+
+/// Dithering hides the banding of gradients when colors are quantized to the 256 or 16 color palette
+/// it does nothing with ColorDepth::TrueColor and ColorDepth::Monochrome
+/// only RenderMode::Character and RenderMode::HalfBlock are dithered, they give every pixel its own color,
+/// the other modes average the pixels of a cell into two colors, which would average the pattern away
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum Dithering {
+    /// every color is quantized to the closest palette color
+    #[default]
+    None,
+    /// an 8x8 Bayer matrix, the pattern is tied to the position on the screen,
+    /// so pixels that don't change aren't redrawn
+    Ordered,
+    /// Floyd–Steinberg error diffusion, smoother than ordered dithering,
+    /// but a change anywhere in a row can change the rest of the frame, so more of it is redrawn
+    FloydSteinberg,
+}
+
+/// the 8x8 Bayer threshold matrix, values are 0 to 63
+const BAYER_MATRIX: [[u8; 8]; 8] = [
+    [0, 32, 8, 40, 2, 34, 10, 42],
+    [48, 16, 56, 24, 50, 18, 58, 26],
+    [12, 44, 4, 36, 14, 46, 6, 38],
+    [60, 28, 52, 20, 62, 30, 54, 22],
+    [3, 35, 11, 43, 1, 33, 9, 41],
+    [51, 19, 59, 27, 49, 17, 57, 25],
+    [15, 47, 7, 39, 13, 45, 5, 37],
+    [63, 31, 55, 23, 61, 29, 53, 21],
+];
+
+/// the size of the ordered dither offsets for every channel of the color,
+/// the distance between the two levels of the palette the channel lies between
+fn ordered_spread(color_depth: ColorDepth, color: Color) -> [f32; 3] {
+    // the levels of the color cube are 95 apart at the dark end and 40 apart after that
+    let cube_gap = |channel: u8| {
+        color_depth::ANSI256_CUBE_LEVELS
+            .windows(2)
+            .find(|levels| channel <= levels[1])
+            .map_or(40.0, |levels| (levels[1] - levels[0]) as f32)
+    };
+    match color_depth {
+        ColorDepth::Ansi256 => [cube_gap(color.r), cube_gap(color.g), cube_gap(color.b)],
+        ColorDepth::Ansi16 => [128.0; 3],
+        // these aren't dithered
+        ColorDepth::TrueColor | ColorDepth::Monochrome => [0.0; 3],
+    }
+}
+
+/// dithers the pixel grid into the dithered pixel grid, the colors of the result are palette colors
+/// returns false if there is nothing to dither, the dithered pixel grid is left unchanged then
+/// transparent pixels are left as they are
+pub(crate) fn dither_pixel_grid(
+    dithering: Dithering,
+    color_depth: ColorDepth,
+    render_mode: RenderMode,
+    pixel_grid: &Frame,
+    dithered_pixel_grid: &mut Frame,
+) -> bool {
+    if !matches!(render_mode, RenderMode::Character | RenderMode::HalfBlock) {
+        return false;
+    }
+    if !matches!(color_depth, ColorDepth::Ansi256 | ColorDepth::Ansi16) {
+        return false;
+    }
+    match dithering {
+        Dithering::None => return false,
+        Dithering::Ordered => {
+            dithered_pixel_grid.clone_from(pixel_grid);
+            for (y, row) in dithered_pixel_grid.rows_mut().enumerate() {
+                for (x, pixel) in row.iter_mut().enumerate() {
+                    if pixel.a == 0.0 {
+                        continue;
+                    }
+                    let threshold = (BAYER_MATRIX[y % 8][x % 8] as f32 + 0.5) / 64.0 - 0.5;
+                    let offsets =
+                        ordered_spread(color_depth, *pixel).map(|spread| threshold * spread);
+                    *pixel = color_depth.quantize(offset_color(*pixel, offsets));
+                }
+            }
+        }
+        Dithering::FloydSteinberg => {
+            dithered_pixel_grid.clone_from(pixel_grid);
+            let width = pixel_grid.width();
+            // the error carried to the current row and the next row, one extra column on both sides
+            let mut current_errors = vec![[0.0f32; 3]; width + 2];
+            let mut next_errors = vec![[0.0f32; 3]; width + 2];
+            for row in dithered_pixel_grid.rows_mut() {
+                for (x, pixel) in row.iter_mut().enumerate() {
+                    if pixel.a == 0.0 {
+                        continue;
+                    }
+                    let wanted = offset_color(*pixel, current_errors[x + 1]);
+                    let quantized = color_depth.quantize(wanted);
+                    let error = [
+                        wanted.r as f32 - quantized.r as f32,
+                        wanted.g as f32 - quantized.g as f32,
+                        wanted.b as f32 - quantized.b as f32,
+                    ];
+                    for channel in 0..3 {
+                        current_errors[x + 2][channel] += error[channel] * 7.0 / 16.0;
+                        next_errors[x][channel] += error[channel] * 3.0 / 16.0;
+                        next_errors[x + 1][channel] += error[channel] * 5.0 / 16.0;
+                        next_errors[x + 2][channel] += error[channel] / 16.0;
+                    }
+                    *pixel = quantized;
+                }
+                std::mem::swap(&mut current_errors, &mut next_errors);
+                next_errors.fill([0.0; 3]);
+            }
+        }
+    }
+    true
+}
+
+/// adds the offsets to the color channels, clamped to 0 to 255
+fn offset_color(color: Color, offsets: [f32; 3]) -> Color {
+    let offset =
+        |channel: u8, offset: f32| (channel as f32 + offset).round().clamp(0.0, 255.0) as u8;
+    Color {
+        r: offset(color.r, offsets[0]),
+        g: offset(color.g, offsets[1]),
+        b: offset(color.b, offsets[2]),
+        a: color.a,
+    }
+}
+
+#[cfg(test)]
+mod dithering_tests {
+    #[test]
+    fn dithering_mixes_palette_colors_to_match_the_average() {
+        use crate::{dithering::dither_pixel_grid, *};
+
+        // a gray between two levels of the color cube, 95 and 135
+        let gray = Color {
+            r: 115,
+            g: 115,
+            b: 115,
+            a: 1.0,
+        };
+        let pixel_grid = FrameBuffer::new(16, 16, gray);
+        let mut dithered = Frame::default();
+        assert!(!dither_pixel_grid(
+            Dithering::Ordered,
+            ColorDepth::TrueColor,
+            RenderMode::Character,
+            &pixel_grid,
+            &mut dithered
+        ));
+
+        for dithering in [Dithering::Ordered, Dithering::FloydSteinberg] {
+            assert!(dither_pixel_grid(
+                dithering,
+                ColorDepth::Ansi256,
+                RenderMode::Character,
+                &pixel_grid,
+                &mut dithered
+            ));
+            let pixels = dithered.as_slice();
+            assert!(pixels
+                .iter()
+                .all(|pixel| ColorDepth::Ansi256.quantize(*pixel) == *pixel));
+            assert!(pixels.iter().any(|pixel| *pixel != pixels[0]));
+            let average =
+                pixels.iter().map(|pixel| pixel.r as f32).sum::<f32>() / pixels.len() as f32;
+            assert!(
+                (average - 115.0).abs() < 5.0,
+                "{:?}: {}",
+                dithering,
+                average
+            );
+        }
+    }
+
+    #[test]
+    fn ordered_dithering_covers_the_wide_gap_at_the_dark_end_of_the_cube() {
+        use crate::{dithering::dither_pixel_grid, *};
+
+        // red between the cube levels 0 and 95, green and blue keep it away from the gray ramp
+        let color = Color {
+            r: 40,
+            g: 255,
+            b: 255,
+            a: 1.0,
+        };
+        let pixel_grid = FrameBuffer::new(16, 16, color);
+        let mut dithered = Frame::default();
+        assert!(dither_pixel_grid(
+            Dithering::Ordered,
+            ColorDepth::Ansi256,
+            RenderMode::Character,
+            &pixel_grid,
+            &mut dithered
+        ));
+        let pixels = dithered.as_slice();
+        let average = pixels.iter().map(|pixel| pixel.r as f32).sum::<f32>() / pixels.len() as f32;
+        assert!((average - 40.0).abs() < 3.0, "{}", average);
+    }
+
+    #[test]
+    fn ordered_dithering_is_stable_between_frames() {
+        use crate::*;
+
+        let backend = MemoryBackend::new();
+        backend.set_terminal_size(Some((32, 8)));
+        let mut renderer = Renderer::with_backend(backend.clone());
+        renderer.set_color_depth(ColorDepth::Ansi256);
+        renderer.set_dithering(Dithering::Ordered);
+
+        let mut pixel_grid = FrameBuffer::new(32, 8, Color::default());
+        for row in pixel_grid.rows_mut() {
+            for (x, pixel) in row.iter_mut().enumerate() {
+                pixel.r = (x * 8) as u8;
+            }
+        }
+        let scene_params = renderer.get_scene_params();
+        ascii_renderer::render_pixel_grid(&mut renderer, &pixel_grid, &scene_params);
+        assert!(!backend.take().is_empty());
+
+        // the dither pattern doesn't move, so only the cell that changed is written again
+        pixel_grid[4][10].r = 255;
+        ascii_renderer::render_pixel_grid(&mut renderer, &pixel_grid, &scene_params);
+        let output = String::from_utf8(backend.take()).unwrap();
+        assert_eq!(output.matches('=').count(), 1);
+    }
+
+    #[test]
+    fn modes_with_several_pixels_per_cell_are_not_dithered() {
+        use crate::*;
+
+        let mut pixel_grid = FrameBuffer::new(32, 16, Color::default());
+        for row in pixel_grid.rows_mut() {
+            for (x, pixel) in row.iter_mut().enumerate() {
+                pixel.r = (x * 8) as u8;
+            }
+        }
+        for render_mode in [
+            RenderMode::Braille,
+            RenderMode::Quadrant,
+            RenderMode::Sextant,
+        ] {
+            let outputs = [Dithering::None, Dithering::Ordered].map(|dithering| {
+                let backend = MemoryBackend::new();
+                backend.set_terminal_size(Some((32, 16)));
+                let mut renderer = Renderer::with_backend(backend.clone());
+                renderer.set_render_mode(render_mode);
+                renderer.set_color_depth(ColorDepth::Ansi256);
+                renderer.set_dithering(dithering);
+                let scene_params = renderer.get_scene_params();
+                ascii_renderer::render_pixel_grid(&mut renderer, &pixel_grid, &scene_params);
+                backend.take()
+            });
+            assert_eq!(outputs[0], outputs[1], "{:?}", render_mode);
+        }
+    }
+}
@@ -0,0 +1,212 @@
+use std::ops::{Index, IndexMut, Range};
+
+/// A 2d grid stored in one contiguous Vec, indexing it returns a row so pixels are accessed as grid[y][x]
+/// resizing keeps the allocation, so a buffer can be reused across frames without allocating
+#[derive(Clone, Debug, PartialEq)]
+pub struct FrameBuffer<T> {
+    width: usize,
+    height: usize,
+    data: Vec<T>,
+}
+
+impl<T> Default for FrameBuffer<T> {
+    fn default() -> Self {
+        FrameBuffer {
+            width: 0,
+            height: 0,
+            data: vec![],
+        }
+    }
+}
+
+impl<T: Clone> FrameBuffer<T> {
+    /// creates a buffer where every cell is the given value
+    pub fn new(width: usize, height: usize, value: T) -> Self {
+        FrameBuffer {
+            width,
+            height,
+            data: vec![value; width * height],
+        }
+    }
+
+    /// resizes the buffer and sets every cell to the given value, reusing the allocation when it is big enough
+    pub fn reset(&mut self, width: usize, height: usize, value: T) {
+        self.width = width;
+        self.height = height;
+        self.data.clear();
+        self.data.resize(width * height, value);
+    }
+
+    /// sets every cell to the given value
+    pub fn fill(&mut self, value: T) {
+        self.data.fill(value);
+    }
+}
+
+impl<T> FrameBuffer<T> {
+    pub fn width(&self) -> usize {
+        self.width
+    }
+
+    pub fn height(&self) -> usize {
+        self.height
+    }
+
+    /// returns true if the buffer has no cells
+    pub fn is_empty(&self) -> bool {
+        self.data.is_empty()
+    }
+
+    /// the cell at (x, y), None if it is out of bounds
+    pub fn get(&self, x: usize, y: usize) -> Option<&T> {
+        if x < self.width && y < self.height {
+            self.data.get(y * self.width + x)
+        } else {
+            None
+        }
+    }
+
+    pub fn row(&self, y: usize) -> &[T] {
+        &self.data[y * self.width..(y + 1) * self.width]
+    }
+
+    pub fn row_mut(&mut self, y: usize) -> &mut [T] {
+        &mut self.data[y * self.width..(y + 1) * self.width]
+    }
+
+    /// iterates over the rows from top to bottom
+    pub fn rows(&self) -> impl Iterator<Item = &[T]> {
+        self.data.chunks(chunk_size(self.width))
+    }
+
+    /// iterates over the rows from top to bottom
+    pub fn rows_mut(&mut self) -> impl Iterator<Item = &mut [T]> {
+        self.data.chunks_mut(chunk_size(self.width))
+    }
+
+    /// every cell in row order
+    pub fn as_slice(&self) -> &[T] {
+        &self.data
+    }
+
+    /// every cell in row order
+    pub fn as_mut_slice(&mut self) -> &mut [T] {
+        &mut self.data
+    }
+
+    /// the whole buffer as a band whose first row is first_row, so it can stand in for a band of a bigger frame
+    pub fn as_band_mut(&mut self, first_row: usize) -> FrameBand<'_, T> {
+        FrameBand {
+            width: self.width,
+            rows: first_row..first_row + self.height,
+            data: &mut self.data,
+        }
+    }
+
+    /// splits the buffer into horizontal bands of band_height rows, the last band may be shorter
+    pub fn bands_mut(&mut self, band_height: usize) -> impl Iterator<Item = FrameBand<'_, T>> {
+        let (width, height) = (self.width, self.height);
+        self.data
+            .chunks_mut(chunk_size(band_height * width))
+            .enumerate()
+            .map(move |(index, data)| {
+                let first_row = index * band_height;
+                FrameBand {
+                    width,
+                    rows: first_row..(first_row + band_height).min(height),
+                    data,
+                }
+            })
+    }
+}
+
+/// the size of the chunks the data is split into for rows or bands of the given number of cells
+/// chunks panics on 0, an empty buffer has no rows or bands anyways
+fn chunk_size(cells: usize) -> usize {
+    cells.max(1)
+}
+
+/// A horizontal band of rows borrowed from a FrameBuffer
+/// it is indexed with the row numbers of the whole buffer, so band[y][x] is the same cell as buffer[y][x]
+pub struct FrameBand<'a, T> {
+    width: usize,
+    rows: Range<usize>,
+    data: &'a mut [T],
+}
+
+impl<T> FrameBand<'_, T> {
+    pub fn width(&self) -> usize {
+        self.width
+    }
+
+    /// the rows of the buffer that are in this band
+    pub fn rows(&self) -> Range<usize> {
+        self.rows.clone()
+    }
+}
+
+impl<T> Index<usize> for FrameBand<'_, T> {
+    type Output = [T];
+
+    fn index(&self, y: usize) -> &[T] {
+        let start = (y - self.rows.start) * self.width;
+        &self.data[start..start + self.width]
+    }
+}
+
+impl<T> IndexMut<usize> for FrameBand<'_, T> {
+    fn index_mut(&mut self, y: usize) -> &mut [T] {
+        let start = (y - self.rows.start) * self.width;
+        &mut self.data[start..start + self.width]
+    }
+}
+
+impl<T> Index<usize> for FrameBuffer<T> {
+    type Output = [T];
+
+    fn index(&self, y: usize) -> &[T] {
+        self.row(y)
+    }
+}
+
+impl<T> IndexMut<usize> for FrameBuffer<T> {
+    fn index_mut(&mut self, y: usize) -> &mut [T] {
+        self.row_mut(y)
+    }
+}
+
+/// converts a grid of rows, every row must have the same length
+impl<T> From<Vec<Vec<T>>> for FrameBuffer<T> {
+    fn from(rows: Vec<Vec<T>>) -> Self {
+        let height = rows.len();
+        let width = rows.first().map_or(0, |row| row.len());
+        assert!(
+            rows.iter().all(|row| row.len() == width),
+            "every row of a frame buffer must have the same length"
+        );
+        FrameBuffer {
+            width,
+            height,
+            data: rows.into_iter().flatten().collect(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod frame_buffer_tests {
+    #[test]
+    fn reset_reuses_the_allocation() {
+        use crate::*;
+
+        let mut frame = FrameBuffer::from(vec![vec![0, 1, 2], vec![3, 4, 5]]);
+        assert_eq!((frame.width(), frame.height()), (3, 2));
+        assert_eq!(frame[1][2], 5);
+        assert_eq!(frame.get(2, 1), Some(&5));
+        assert_eq!(frame.get(3, 0), None);
+
+        let data = frame.as_slice().as_ptr();
+        frame.reset(2, 3, 7);
+        assert_eq!(frame.rows().collect::<Vec<_>>(), vec![&[7, 7][..]; 3]);
+        assert_eq!(frame.as_slice().as_ptr(), data);
+    }
+}
@@ -0,0 +1,221 @@
+use crate::*;
+use std::collections::VecDeque;
+use std::io::Write;
+use std::path::Path;
+use std::time::{Duration, Instant};
+
+/// How many frames a capture keeps, the oldest frames are dropped once it is full
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum CaptureLength {
+    /// keeps the last n frames, the capture keeps running and older frames are dropped
+    LastFrames(usize),
+    /// keeps the frames of the last few seconds, for instant replays
+    Duration(Duration),
+}
+
+/// a ring buffer of composed frames and the time they were started
+pub(crate) struct FrameCapture {
+    length: CaptureLength,
+    frames: VecDeque<(Instant, Frame)>,
+}
+
+impl FrameCapture {
+    pub(crate) fn new(length: CaptureLength) -> FrameCapture {
+        FrameCapture {
+            length,
+            frames: VecDeque::new(),
+        }
+    }
+
+    /// adds a copy of the frame, reusing the buffer of the oldest frame when the capture is full
+    /// a gif can't change size, so the capture starts over if the size of the frame changed
+    pub(crate) fn push(&mut self, frame_start: Instant, frame: &Frame) {
+        if let Some((_, last_frame)) = self.frames.back() {
+            if last_frame.width() != frame.width() || last_frame.height() != frame.height() {
+                self.frames.clear();
+            }
+        }
+
+        let mut buffer = match self.length {
+            CaptureLength::LastFrames(frames) if self.frames.len() >= frames.max(1) => {
+                self.frames.pop_front().map(|(_, buffer)| buffer)
+            }
+            _ => None,
+        }
+        .unwrap_or_default();
+        buffer.clone_from(frame);
+        self.frames.push_back((frame_start, buffer));
+
+        if let CaptureLength::Duration(duration) = self.length {
+            while let Some((oldest_start, _)) = self.frames.front() {
+                if frame_start.duration_since(*oldest_start) <= duration {
+                    break;
+                }
+                self.frames.pop_front();
+            }
+        }
+    }
+
+    pub(crate) fn len(&self) -> usize {
+        self.frames.len()
+    }
+
+    /// encodes the frames as an animated gif that loops forever
+    /// every frame is shown until the next one started, the last frame is shown as long as the one before it
+    pub(crate) fn write_gif(
+        &self,
+        output: impl Write,
+        pixel_width: u32,
+        pixel_height: u32,
+    ) -> Result<(), RenderError> {
+        use image::codecs::gif::{GifEncoder, Repeat};
+
+        let mut encoder = GifEncoder::new(output);
+        encoder.set_repeat(Repeat::Infinite)?;
+        let mut last_delay = Duration::from_millis(100);
+        for (i, (frame_start, frame)) in self.frames.iter().enumerate() {
+            if let Some((next_start, _)) = self.frames.get(i + 1) {
+                last_delay = next_start.duration_since(*frame_start);
+            }
+            let image = screenshot::frame_to_image(frame, pixel_width, pixel_height);
+            encoder.encode_frame(image::Frame::from_parts(
+                image,
+                0,
+                0,
+                image::Delay::from_saturating_duration(last_delay),
+            ))?;
+        }
+        Ok(())
+    }
+}
+
+impl Renderer {
+    /// starts keeping a copy of every frame drawn by render and try_render, see save_capture_gif
+    /// frames composed with render_to_grid aren't captured, they are never shown
+    /// a running capture is started over
+    pub fn start_capture(&mut self, length: CaptureLength) {
+        self.frame_capture = Some(FrameCapture::new(length));
+    }
+
+    /// stops capturing and drops the captured frames
+    pub fn stop_capture(&mut self) {
+        self.frame_capture = None;
+    }
+
+    pub fn is_capturing(&self) -> bool {
+        self.frame_capture.is_some()
+    }
+
+    /// the number of frames in the capture, 0 if nothing is being captured
+    pub fn get_captured_frames(&self) -> usize {
+        self.frame_capture.as_ref().map_or(0, FrameCapture::len)
+    }
+
+    /// saves the captured frames as an animated gif, the capture keeps going
+    /// returns RenderError::NothingCaptured without creating the file if there are no captured frames
+    pub fn save_capture_gif(
+        &self,
+        path: impl AsRef<Path>,
+        mode: ScreenshotMode,
+    ) -> Result<(), RenderError> {
+        if self.get_captured_frames() == 0 {
+            return Err(RenderError::NothingCaptured);
+        }
+        let file = std::io::BufWriter::new(std::fs::File::create(path)?);
+        self.write_capture_gif(file, mode)
+    }
+
+    /// writes the captured frames as an animated gif, the capture keeps going
+    /// returns RenderError::NothingCaptured without writing anything if there are no captured frames
+    pub fn write_capture_gif(
+        &self,
+        output: impl Write,
+        mode: ScreenshotMode,
+    ) -> Result<(), RenderError> {
+        let Some(frame_capture) = self
+            .frame_capture
+            .as_ref()
+            .filter(|capture| capture.len() > 0)
+        else {
+            return Err(RenderError::NothingCaptured);
+        };
+        let (pixel_width, pixel_height) = self.screenshot_pixel_size(mode);
+        frame_capture.write_gif(output, pixel_width, pixel_height)?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod frame_capture_tests {
+    #[test]
+    fn captures_keep_the_latest_frames_with_their_delays() {
+        use crate::{frame_capture::FrameCapture, *};
+        use image::AnimationDecoder;
+        use std::time::{Duration, Instant};
+
+        let start = Instant::now();
+        let frame = |shade: u8| {
+            FrameBuffer::new(
+                3,
+                2,
+                Color {
+                    r: shade,
+                    g: shade,
+                    b: shade,
+                    a: 1.0,
+                },
+            )
+        };
+
+        let mut capture = FrameCapture::new(CaptureLength::LastFrames(2));
+        for (i, shade) in [0, 100, 200].into_iter().enumerate() {
+            capture.push(start + Duration::from_millis(50 * i as u64), &frame(shade));
+        }
+        assert_eq!(capture.len(), 2);
+
+        let mut gif = vec![];
+        capture.write_gif(&mut gif, 2, 2).unwrap();
+        let frames = image::codecs::gif::GifDecoder::new(std::io::Cursor::new(gif))
+            .unwrap()
+            .into_frames()
+            .collect_frames()
+            .unwrap();
+        assert_eq!(frames.len(), 2);
+        for (frame, shade) in frames.iter().zip([100, 200]) {
+            assert_eq!(frame.buffer().dimensions(), (6, 4));
+            assert_eq!(frame.buffer().get_pixel(5, 3).0, [shade, shade, shade, 255]);
+            assert_eq!(frame.delay().numer_denom_ms(), (50, 1));
+        }
+
+        let mut capture = FrameCapture::new(CaptureLength::Duration(Duration::from_millis(120)));
+        for i in 0..10 {
+            capture.push(start + Duration::from_millis(50 * i), &frame(0));
+        }
+        assert_eq!(capture.len(), 3);
+        // a frame of a different size starts the capture over
+        capture.push(
+            start + Duration::from_millis(500),
+            &FrameBuffer::new(1, 1, Color::default()),
+        );
+        assert_eq!(capture.len(), 1);
+    }
+
+    #[test]
+    fn saving_without_captured_frames_is_an_error() {
+        use crate::*;
+
+        let path = std::env::temp_dir().join("console_renderer_nothing_captured_test.gif");
+        let mut renderer = Renderer::headless();
+        assert!(matches!(
+            renderer.save_capture_gif(&path, ScreenshotMode::Pixels),
+            Err(RenderError::NothingCaptured)
+        ));
+
+        renderer.start_capture(CaptureLength::LastFrames(10));
+        assert!(matches!(
+            renderer.save_capture_gif(&path, ScreenshotMode::Pixels),
+            Err(RenderError::NothingCaptured)
+        ));
+        assert!(!path.exists());
+    }
+}
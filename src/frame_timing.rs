@@ -0,0 +1,90 @@
+use std::time::{Duration, Instant};
+use ABC_Game_Engine::Resource;
+
+/// How long the last frame took to compose and write, added to the scene as a resource by Renderer::render
+/// the time between frames is in the engine's DeltaTime resource
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct FrameTiming {
+    /// the time it took to compose and write the last frame, without the time spent waiting
+    pub frame_time: Duration,
+}
+
+impl Resource for FrameTiming {
+    fn update(&mut self) {}
+    fn as_any(&self) -> &dyn std::any::Any {
+        self
+    }
+    fn as_any_mut(&mut self) -> &mut dyn std::any::Any {
+        self
+    }
+}
+
+/// marks a scene whose DeltaTime resource was added by a renderer, the engine advances the DeltaTime of its own scenes
+pub(crate) struct RendererDeltaTime;
+
+impl Resource for RendererDeltaTime {
+    fn update(&mut self) {}
+    fn as_any(&self) -> &dyn std::any::Any {
+        self
+    }
+    fn as_any_mut(&mut self) -> &mut dyn std::any::Any {
+        self
+    }
+}
+
+/// keeps frames at a steady cadence and measures how long they take
+#[derive(Default)]
+pub(crate) struct FramePacer {
+    // None means frames are rendered as fast as possible
+    pub(crate) target_frame_time: Option<Duration>,
+    frame_start: Option<Instant>,
+    // the start of the first frame, the time post processing passes get is measured from it
+    first_frame_start: Option<Instant>,
+    pub(crate) delta_time: Duration,
+    pub(crate) frame_time: Duration,
+}
+
+impl FramePacer {
+    /// sleeps until the target frame time has passed since the start of the last frame
+    pub(crate) fn wait_for_next_frame(&self) {
+        if let (Some(target_frame_time), Some(frame_start)) =
+            (self.target_frame_time, self.frame_start)
+        {
+            let next_frame = frame_start + target_frame_time;
+            let now = Instant::now();
+            if next_frame > now {
+                std::thread::sleep(next_frame - now);
+            }
+        }
+    }
+
+    /// marks the start of a new frame and measures the delta time since the last one
+    pub(crate) fn start_frame(&mut self) {
+        let now = Instant::now();
+        self.delta_time = self
+            .frame_start
+            .map_or(Duration::ZERO, |frame_start| now - frame_start);
+        self.frame_start = Some(now);
+        self.first_frame_start.get_or_insert(now);
+    }
+
+    /// measures the frame time since the start of the frame
+    pub(crate) fn end_frame(&mut self) {
+        if let Some(frame_start) = self.frame_start {
+            self.frame_time = frame_start.elapsed();
+        }
+    }
+
+    /// the time from the start of the first frame to the start of the current frame
+    pub(crate) fn elapsed(&self) -> Duration {
+        match (self.first_frame_start, self.frame_start) {
+            (Some(first_frame_start), Some(frame_start)) => frame_start - first_frame_start,
+            _ => Duration::ZERO,
+        }
+    }
+
+    /// the time the current frame started, every animation in a frame is advanced using the same time
+    pub(crate) fn frame_start(&self) -> Instant {
+        self.frame_start.unwrap_or_else(Instant::now)
+    }
+}
@@ -0,0 +1,160 @@
+use crate::*;
+use std::fmt::Write;
+use std::path::Path;
+
+impl Renderer {
+    /// turns the last composed frame into a standalone html page, a monospace pre of colored spans
+    /// uses the same glyphs, render mode, pixel scale, color depth and dithering as the terminal
+    pub fn export_html(&self) -> String {
+        let pixel_scale = self.renderer_params.pixel_scale as usize;
+        let mut scaled_pixel_grid = Frame::default();
+        let pixel_grid = if pixel_scale != 1 {
+            ascii_renderer::scale_pixel_grid(&self.frame, pixel_scale, &mut scaled_pixel_grid);
+            &scaled_pixel_grid
+        } else {
+            &self.frame
+        };
+        let mut dithered_pixel_grid = Frame::default();
+        let pixel_grid = if dither_pixel_grid(
+            self.renderer_params.dithering,
+            self.renderer_params.color_depth,
+            self.renderer_params.render_mode,
+            pixel_grid,
+            &mut dithered_pixel_grid,
+        ) {
+            &dithered_pixel_grid
+        } else {
+            pixel_grid
+        };
+
+        frame_to_html(
+            pixel_grid,
+            self.renderer_params.render_mode,
+            self.renderer_params.color_depth,
+            &self.scene_params,
+        )
+    }
+
+    /// saves the last composed frame as a standalone html page, see export_html
+    pub fn save_html(&self, path: impl AsRef<Path>) -> Result<(), RenderError> {
+        std::fs::write(path, self.export_html())?;
+        Ok(())
+    }
+}
+
+/// writes every cell of the pixel grid into a pre, cells next to each other with the same colors share a span
+/// cells without a color use the page's default colors like they use the terminal's default colors
+fn frame_to_html(
+    pixel_grid: &Frame,
+    render_mode: RenderMode,
+    color_depth: ColorDepth,
+    scene_params: &SceneParams,
+) -> String {
+    let (cell_width, cell_height) = render_mode.pixels_per_cell();
+    let (cell_width, cell_height) = (cell_width as usize, cell_height as usize);
+    let (grid_width, grid_height) = (pixel_grid.width(), pixel_grid.height());
+
+    let mut html = String::from(
+        "<!DOCTYPE html>\n<html>\n<head>\n<meta charset=\"utf-8\">\n<style>\n\
+         pre { background: #000000; color: #c0c0c0; font-family: monospace; line-height: 1; }\n\
+         </style>\n</head>\n<body>\n<pre>",
+    );
+    for row in (0..grid_height).step_by(cell_height) {
+        // the colors of the span that is currently open, None if no span is open
+        let mut span: Option<(Option<Color>, Option<Color>)> = None;
+        for column in (0..grid_width).step_by(cell_width) {
+            let rows = row..(row + cell_height).min(grid_height);
+            let columns = column..(column + cell_width).min(grid_width);
+            let (glyph, foreground, background) =
+                ascii_renderer::cell_contents(render_mode, pixel_grid, rows, columns, scene_params);
+
+            let colors = if color_depth == ColorDepth::Monochrome {
+                (None, None)
+            } else {
+                (
+                    foreground.map(|color| color_depth.quantize(color)),
+                    background.map(|color| color_depth.quantize(color)),
+                )
+            };
+            if span != Some(colors) {
+                if span.is_some_and(|colors| colors != (None, None)) {
+                    html.push_str("</span>");
+                }
+                write_span_start(&mut html, colors.0, colors.1);
+                span = Some(colors);
+            }
+
+            match glyph {
+                '<' => html.push_str("&lt;"),
+                '>' => html.push_str("&gt;"),
+                '&' => html.push_str("&amp;"),
+                glyph => html.push(glyph),
+            }
+        }
+        if span.is_some_and(|colors| colors != (None, None)) {
+            html.push_str("</span>");
+        }
+        html.push('\n');
+    }
+    html.push_str("</pre>\n</body>\n</html>\n");
+    html
+}
+
+/// opens a span with the colors, nothing is opened if both are the default color
+fn write_span_start(html: &mut String, foreground: Option<Color>, background: Option<Color>) {
+    if foreground.is_none() && background.is_none() {
+        return;
+    }
+    html.push_str("<span style=\"");
+    if let Some(color) = foreground {
+        let _ = write!(
+            html,
+            "color: #{:02x}{:02x}{:02x};",
+            color.r, color.g, color.b
+        );
+    }
+    if let Some(color) = background {
+        if foreground.is_some() {
+            html.push(' ');
+        }
+        let _ = write!(
+            html,
+            "background: #{:02x}{:02x}{:02x};",
+            color.r, color.g, color.b
+        );
+    }
+    html.push_str("\">");
+}
+
+#[cfg(test)]
+mod html_export_tests {
+    #[test]
+    fn frames_are_exported_as_colored_spans() {
+        use crate::{test_helpers::*, *};
+
+        let mut pixel_grid = FrameBuffer::new(3, 2, RED);
+        pixel_grid[1][0] = BLUE;
+        pixel_grid[1][2] = Color { a: 0.0, ..RED };
+
+        let mut renderer = Renderer::headless();
+        renderer.set_color_depth(ColorDepth::TrueColor);
+        renderer.set_scene_params(SceneParams::new().with_character('<'));
+        renderer.frame = pixel_grid;
+        let html = renderer.export_html();
+        assert!(html.starts_with("<!DOCTYPE html>"));
+        assert!(html.contains(
+            "<pre><span style=\"color: #ff0000;\">&lt;&lt;&lt;</span>\n\
+             <span style=\"color: #0000ff;\">&lt;</span><span style=\"color: #ff0000;\">&lt;</span> \n\
+             </pre>"
+        ));
+
+        // half blocks keep the top pixel in front and the bottom pixel in the back
+        renderer.set_render_mode(RenderMode::HalfBlock);
+        let html = renderer.export_html();
+        assert!(html.contains(
+            "<pre><span style=\"color: #ff0000; background: #0000ff;\">▀</span>\
+             <span style=\"color: #ff0000; background: #ff0000;\">▀</span>\
+             <span style=\"color: #ff0000;\">▀</span>\n</pre>"
+        ));
+    }
+}
@@ -8,6 +8,8 @@ use ABC_Game_Engine::Input;
 use ABC_Game_Engine::KeyCode;
 use ABC_Game_Engine::Resource;
 
+use crate::RenderError;
+
 /// updates the input system
 pub(crate) struct ConsoleInput {
     receiver: EventReceiver,
@@ -15,21 +17,24 @@ pub(crate) struct ConsoleInput {
 }
 
 impl ConsoleInput {
-    pub(crate) fn new() -> Self {
-        let receiver =
-            message_loop::start().expect("failed to start message loop for input system");
+    pub(crate) fn new() -> Result<Self, RenderError> {
+        let receiver = message_loop::start()
+            .map_err(|error| RenderError::InputUnavailable(format!("{:?}", error)))?;
         let keys_down = HashSet::new();
-        Self {
+        Ok(Self {
             receiver,
             keys_down,
-        }
+        })
     }
 
     /// main update loop for the input system
-    pub(crate) fn update(&mut self, entities_and_components: &mut EntitiesAndComponents) {
+    pub(crate) fn update(
+        &mut self,
+        entities_and_components: &mut EntitiesAndComponents,
+    ) -> Result<(), RenderError> {
         let input = entities_and_components
             .get_resource_mut::<Input>()
-            .expect("failed to get input resource from entities and components");
+            .ok_or(RenderError::MissingInputResource)?;
 
         input.clear_key_states();
         if let Some(next_event) = self.receiver.try_next_event() {
@@ -60,6 +65,7 @@ impl ConsoleInput {
         for key in self.keys_down.iter() {
             input.set_key_down(*key)
         }
+        Ok(())
     }
 }
 
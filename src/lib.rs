@@ -1,23 +1,48 @@
 pub mod camera;
 use camera::Camera;
-use colored::Colorize;
 use crossterm::cursor;
 use rand::Rng;
 use std::{
     io::Write,
+    ops::Range,
+    sync::Arc,
     time::{Duration, Instant},
     vec,
 };
-use ABC_Game_Engine::{EntitiesAndComponents, Entity, System};
+use ABC_Game_Engine::{DeltaTime, EntitiesAndComponents, Entity, Resource, System};
 
 mod input;
 
 use self::mask::Mask;
 pub mod ascii_renderer;
+mod cast_recorder;
+mod color_depth;
+pub use color_depth::*;
+mod dithering;
+pub use dithering::*;
+mod frame_buffer;
+pub use frame_buffer::*;
+mod frame_capture;
+pub use frame_capture::*;
+mod frame_timing;
+pub use frame_timing::*;
+mod html_export;
 mod load_texture;
 pub use load_texture::*;
 pub mod mask;
+mod post_process;
+pub use post_process::*;
+mod render_backend;
+pub use render_backend::*;
+mod render_error;
+pub use render_error::*;
+mod screenshot;
+pub use screenshot::*;
 mod shape_renderer;
+mod terminal_session;
+pub use terminal_session::*;
+#[cfg(test)]
+mod test_helpers;
 use ABC_Game_Engine::Transform;
 
 #[derive(Clone, Copy, Debug, PartialEq)]
@@ -52,9 +77,12 @@ pub struct Rectangle {
     pub color: Color,
 }
 
+/// a composed frame, indexed as frame[y][x]
+pub type Frame = FrameBuffer<Color>;
+
 #[derive(Clone)]
 pub struct Texture {
-    pub pixels: Vec<Vec<Color>>, // not sure how inefficient this is but it will do for now
+    pub pixels: FrameBuffer<Color>,
 }
 
 // rectangle with texture
@@ -108,14 +136,63 @@ struct RendererParams {
     height: u32,
     stretch: f32,
     pixel_scale: u16,
+    render_mode: RenderMode,
+    color_depth: ColorDepth,
+    dithering: Dithering,
+    // asking the terminal to resize itself only works on some terminals, so it is opt-in
+    resize_terminal_window: bool,
+    // wraps every frame in a synchronized update so it is shown at once
+    // None follows what the backend supports
+    synchronized_output: Option<bool>,
+    // experimental, 1 rasterizes on the calling thread
+    render_threads: usize,
+}
+
+/// RenderMode decides how the pixels of a frame are mapped onto terminal cells
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum RenderMode {
+    /// one pixel per cell, drawn with the character(s) from SceneParams
+    #[default]
+    Character,
+    /// two vertically stacked pixels per cell, drawn as a half block with a foreground and background color
+    /// doubles the vertical resolution
+    HalfBlock,
+    /// 2x4 pixels per cell, drawn with braille characters for line art and plots
+    /// pixels that match the background color from SceneParams are left empty,
+    /// the rest share one foreground color per cell
+    Braille,
+    /// 2x2 pixels per cell, drawn with quadrant block characters
+    /// each cell picks the two colors that best fit its pixels as the foreground and background
+    Quadrant,
+    /// 2x3 pixels per cell, drawn with the unicode 13 sextant characters, needs a font that supports them
+    /// each cell picks the two colors that best fit its pixels as the foreground and background
+    Sextant,
+}
+
+impl RenderMode {
+    /// the number of pixels (horizontal, vertical) that are packed into a single terminal cell
+    pub fn pixels_per_cell(&self) -> (u32, u32) {
+        match self {
+            RenderMode::Character => (1, 1),
+            RenderMode::HalfBlock => (1, 2),
+            RenderMode::Braille => (2, 4),
+            RenderMode::Quadrant => (2, 2),
+            RenderMode::Sextant => (2, 3),
+        }
+    }
 }
 
+/// a density ramp from dark to light that can be used with SceneParams::set_luminance_ramp
+pub const DEFAULT_LUMINANCE_RAMP: &str = " .:-=+*#%@";
+
 /// SceneParams is a struct that holds the background color, if the characters are random, and the character that will be displayed otherwise
-#[derive(Clone, Copy)]
+#[derive(Clone)]
 pub struct SceneParams {
     background_color: Color,
     is_random_chars: bool,
     character: char,
+    // shared so the renderer can clone the params every frame without copying the ramp
+    luminance_ramp: Option<Arc<str>>,
 }
 
 impl SceneParams {
@@ -129,6 +206,7 @@ impl SceneParams {
             },
             is_random_chars: false,
             character: '=',
+            luminance_ramp: None,
         }
     }
 
@@ -164,6 +242,22 @@ impl SceneParams {
         self.set_character(character);
         self
     }
+
+    /// picks the character of each pixel from a ramp of characters ordered from dark to light based on the pixel's luminance,
+    /// used instead of the character if is_random_chars is false, None goes back to the single character
+    /// the ramp can be a &'static str or a String that was loaded at runtime
+    pub fn set_luminance_ramp(&mut self, luminance_ramp: Option<impl Into<Arc<str>>>) {
+        self.luminance_ramp = luminance_ramp
+            .map(Into::into)
+            .filter(|ramp| !ramp.is_empty());
+    }
+
+    /// picks the character of each pixel from a ramp of characters ordered from dark to light based on the pixel's luminance,
+    /// used instead of the character if is_random_chars is false, None goes back to the single character
+    pub fn with_luminance_ramp(mut self, luminance_ramp: Option<impl Into<Arc<str>>>) -> Self {
+        self.set_luminance_ramp(luminance_ramp);
+        self
+    }
 }
 
 /// Renderer is responsible for rendering the scene
@@ -172,61 +266,296 @@ pub struct Renderer {
     scene_params: SceneParams,
     // used for diffing
     // will be empty if no previous frame
-    last_pixel_grid: Vec<Vec<Color>>,
-    handle: std::io::BufWriter<std::io::StdoutLock<'static>>,
+    last_pixel_grid: Frame,
+    // the buffers below are kept between frames, so they are only allocated while they grow
+    frame: Frame,
+    scaled_pixel_grid: Frame,
+    dithered_pixel_grid: Frame,
+    // the views of cameras that don't cover the whole frame are drawn here first
+    viewport_grid: Frame,
+    // the cameras of the frame, (camera, camera component, x, y) for views and (camera, camera component, render target) for textures
+    camera_views: Vec<(Entity, Camera, usize, usize)>,
+    texture_cameras: Vec<(Entity, Camera, Entity)>,
+    // the lists and mask grids render_objects fills for every camera
+    draw_buffers: DrawBuffers,
+    // the size of the terminal (columns, rows) when it was last checked, None if it isn't a terminal
+    available_cells: Option<(u16, u16)>,
+    // whether the backend supports synchronized updates, used until set_synchronized_output is called
+    backend_synchronized_output: bool,
+    frame_pacer: FramePacer,
+    // copies of the last frames for save_capture_gif, None if nothing is being captured
+    frame_capture: Option<FrameCapture>,
+    // run on every composed frame before it is written
+    post_processes: Vec<Box<dyn PostProcess>>,
+    // why the input wasn't updated on the last frame, None if it was
+    input_error: Option<RenderError>,
+    handle: std::io::BufWriter<BackendWriter>,
 }
 
 impl Renderer {
+    /// creates a new renderer that writes to stdout
     pub fn new() -> Renderer {
-        let stdout = std::io::stdout().lock();
-        let mut handle = std::io::BufWriter::with_capacity(8192, stdout);
-        crossterm::queue!(
-            handle,
-            cursor::Hide,
-            crossterm::terminal::SetSize(160 as u16, 160 as u16),
-            crossterm::terminal::Clear(crossterm::terminal::ClearType::All),
-        )
-        .expect("Error: failed to set terminal size");
+        Renderer::with_backend(std::io::stdout())
+    }
 
-        Renderer {
+    /// creates a new renderer that writes to the given backend
+    /// for example stderr, a file, a socket or a MemoryBackend
+    pub fn with_backend(backend: impl RenderBackend + 'static) -> Renderer {
+        let backend: Box<dyn RenderBackend> = Box::new(backend);
+        let handle = std::io::BufWriter::with_capacity(
+            8192,
+            BackendWriter {
+                backend,
+                recorder: None,
+            },
+        );
+
+        let mut renderer = Renderer {
             renderer_params: RendererParams {
                 width: 160,
                 height: 160,
                 stretch: 2.3,
                 pixel_scale: 1,
+                render_mode: RenderMode::Character,
+                color_depth: ColorDepth::from_env(),
+                dithering: Dithering::None,
+                resize_terminal_window: false,
+                synchronized_output: None,
+                render_threads: 1,
             },
-            scene_params: SceneParams {
-                background_color: Color {
-                    r: 0,
-                    g: 0,
-                    b: 0,
-                    a: 1.0,
-                },
-                is_random_chars: false,
-                character: '=',
-            },
-            last_pixel_grid: vec![],
+            scene_params: SceneParams::new(),
+            last_pixel_grid: Frame::default(),
+            frame: Frame::default(),
+            scaled_pixel_grid: Frame::default(),
+            dithered_pixel_grid: Frame::default(),
+            viewport_grid: Frame::default(),
+            camera_views: vec![],
+            texture_cameras: vec![],
+            draw_buffers: DrawBuffers::default(),
             handle,
-        }
+            available_cells: None,
+            backend_synchronized_output: false,
+            frame_pacer: FramePacer::default(),
+            frame_capture: None,
+            post_processes: vec![],
+            input_error: None,
+        };
+        renderer.available_cells = renderer.handle.get_ref().backend.terminal_size();
+        renderer.backend_synchronized_output = renderer
+            .handle
+            .get_ref()
+            .backend
+            .supports_synchronized_output();
+        renderer.resize_terminal();
+        renderer
+    }
+
+    /// creates a renderer that discards its output, use render_to_grid to get the frames
+    pub fn headless() -> Renderer {
+        Renderer::with_backend(std::io::sink())
+    }
+
+    /// switches the output to a new backend, the next frame will be fully redrawn
+    pub fn set_backend(&mut self, backend: impl RenderBackend + 'static) {
+        // the old backend may already be closed, there is nothing left to do with it anyways
+        let _ = self.handle.flush();
+        let backend: Box<dyn RenderBackend> = Box::new(backend);
+        // a running recording keeps going on the new backend
+        self.handle.get_mut().backend = backend;
+        self.available_cells = self.handle.get_ref().backend.terminal_size();
+        self.backend_synchronized_output =
+            self.handle.get_ref().backend.supports_synchronized_output();
+        self.resize_terminal();
     }
 
     pub fn set_stretch(&mut self, stretch: f32) {
         self.renderer_params.stretch = stretch;
     }
 
+    /// a scale of 0 is treated as 1
     pub fn set_pixel_scale(&mut self, pixel_scale: u16) {
-        self.renderer_params.pixel_scale = pixel_scale;
+        self.renderer_params.pixel_scale = pixel_scale.max(1);
+        self.resize_terminal();
+    }
+
+    /// sets how pixels are mapped onto terminal cells, the screen is cleared and fully redrawn on the next frame
+    pub fn set_render_mode(&mut self, render_mode: RenderMode) {
+        self.renderer_params.render_mode = render_mode;
+        self.resize_terminal();
+    }
+
+    pub fn get_render_mode(&self) -> RenderMode {
+        self.renderer_params.render_mode
+    }
+
+    /// sets how colors are written to the terminal, the screen is cleared and fully redrawn on the next frame
+    /// defaults to ColorDepth::from_env()
+    pub fn set_color_depth(&mut self, color_depth: ColorDepth) {
+        self.renderer_params.color_depth = color_depth;
+        self.resize_terminal();
+    }
+
+    pub fn get_color_depth(&self) -> ColorDepth {
+        self.renderer_params.color_depth
+    }
+
+    /// sets how gradients are dithered when colors are quantized to a palette, off by default
+    /// only the Character and HalfBlock render modes are dithered, see Dithering
+    /// the screen is cleared and fully redrawn on the next frame
+    pub fn set_dithering(&mut self, dithering: Dithering) {
+        self.renderer_params.dithering = dithering;
+        self.resize_terminal();
+    }
+
+    pub fn get_dithering(&self) -> Dithering {
+        self.renderer_params.dithering
+    }
+
+    /// asks the terminal window to resize to fit the camera, off by default
+    /// most linux terminals ignore this, the frame is clipped to the terminal instead
+    pub fn set_resize_terminal_window(&mut self, resize_terminal_window: bool) {
+        self.renderer_params.resize_terminal_window = resize_terminal_window;
+        self.resize_terminal();
+    }
+
+    /// wraps every frame in a synchronized update (DEC mode 2026) so terminals show it at once instead of half drawn
+    /// on by default if the backend supports it, terminals without support ignore it
+    /// the choice is kept when the backend is switched
+    pub fn set_synchronized_output(&mut self, synchronized_output: bool) {
+        self.renderer_params.synchronized_output = Some(synchronized_output);
+    }
+
+    pub fn get_synchronized_output(&self) -> bool {
+        self.renderer_params
+            .synchronized_output
+            .unwrap_or(self.backend_synchronized_output)
+    }
+
+    /// sets how many threads rasterize the sprites, every thread draws a horizontal band of the frame
+    /// defaults to 1, which rasterizes on the calling thread
+    /// more than 1 is experimental, no speedup has been measured yet, the render bench compares the thread counts
+    /// the threads are started and the draw list is allocated for every camera of every frame
+    pub fn set_render_threads(&mut self, render_threads: usize) {
+        self.renderer_params.render_threads = render_threads.max(1);
+    }
+
+    pub fn get_render_threads(&self) -> usize {
+        self.renderer_params.render_threads
+    }
+
+    /// starts recording everything written to the backend as an asciicast v2 file, for example to a File
+    /// the terminal is cleared and the next frame is fully redrawn so the recording starts from a complete frame
+    /// a running recording is stopped first
+    /// the recording keeps the size the terminal had when it started, resizing the terminal adds no resize event
+    pub fn start_recording(&mut self, output: impl Write + 'static) -> Result<(), RenderError> {
+        self.stop_recording()?;
+        // the frame is clipped to the terminal, so the recording doesn't need to be bigger than it
+        let recorder = cast_recorder::CastRecorder::new(Box::new(output), self.visible_cells())?;
+        self.handle.flush()?;
+        self.handle.get_mut().recorder = Some(recorder);
+        self.resize_terminal();
+        Ok(())
+    }
+
+    /// stops the recording and flushes it, returns the first error that happened while writing it
+    pub fn stop_recording(&mut self) -> Result<(), RenderError> {
+        // write out the last frame before the recording stops
+        let flushed = self.handle.flush();
+        if let Some(recorder) = self.handle.get_mut().recorder.take() {
+            recorder.finish()?;
+        }
+        flushed.map_err(RenderError::from)
+    }
+
+    pub fn is_recording(&self) -> bool {
+        self.handle.get_ref().recorder.is_some()
+    }
+
+    /// the size of the terminal in cells (columns, rows), None if the backend is not a terminal
+    pub fn get_terminal_size(&self) -> Option<(u16, u16)> {
+        self.available_cells
+    }
+
+    /// the size of the frame in terminal cells (columns, rows)
+    fn frame_cells(&self) -> (u16, u16) {
+        let (cell_width, cell_height) = self.renderer_params.render_mode.pixels_per_cell();
+        let pixel_scale = self.renderer_params.pixel_scale as u32;
+        (
+            (self.renderer_params.width * pixel_scale).div_ceil(cell_width) as u16,
+            (self.renderer_params.height * pixel_scale).div_ceil(cell_height) as u16,
+        )
+    }
+
+    /// the stretch adjusted for the shape of a pixel inside of a terminal cell
+    fn pixel_stretch(&self) -> f32 {
+        let (cell_width, cell_height) = self.renderer_params.render_mode.pixels_per_cell();
+        self.renderer_params.stretch * cell_width as f32 / cell_height as f32
+    }
 
+    /// the cells of the frame that fit in the terminal (columns, rows), the rest of the frame is clipped
+    fn visible_cells(&self) -> (u16, u16) {
+        let (columns, rows) = self.frame_cells();
+        match self.available_cells {
+            Some((available_columns, available_rows)) => {
+                (columns.min(available_columns), rows.min(available_rows))
+            }
+            None => (columns, rows),
+        }
+    }
+
+    /// checks the size of the terminal, returns true if it changed since the last check
+    fn update_terminal_size(&mut self) -> bool {
+        let available_cells = self.handle.get_ref().backend.terminal_size();
+        let changed = available_cells != self.available_cells;
+        self.available_cells = available_cells;
+        changed
+    }
+
+    /// clears the terminal (and resizes it if enabled), the whole frame is redrawn afterwards
+    /// write errors are ignored, the backend fails again when the next frame is written and reports it then
+    fn resize_terminal(&mut self) {
+        self.last_pixel_grid
+            .reset(0, 0, self.scene_params.background_color);
+        let _ = self.queue_resize_terminal();
+    }
+
+    fn queue_resize_terminal(&mut self) -> std::io::Result<()> {
+        let (columns, rows) = self.frame_cells();
+        crossterm::queue!(self.handle, cursor::Hide)?;
+        if self.renderer_params.resize_terminal_window {
+            crossterm::queue!(self.handle, crossterm::terminal::SetSize(columns, rows))?;
+        }
+        // a frame that failed half way can leave its colors set, the clear would fill the screen with them
+        write!(self.handle, "\x1b[0m")?;
         crossterm::queue!(
             self.handle,
-            cursor::Hide,
-            crossterm::terminal::SetSize(
-                self.renderer_params.width as u16 * self.renderer_params.pixel_scale,
-                self.renderer_params.height as u16 * self.renderer_params.pixel_scale
-            ),
             crossterm::terminal::Clear(crossterm::terminal::ClearType::All),
         )
-        .expect("Error: failed to set terminal size");
+    }
+
+    /// limits how often render writes a frame, render sleeps to hold a steady cadence
+    /// None renders as fast as possible, which is the default
+    /// a rate that isn't above 0 or is too low for its frame time to fit in a Duration is treated as None
+    pub fn set_target_fps(&mut self, target_fps: Option<f32>) {
+        self.frame_pacer.target_frame_time = target_fps
+            .filter(|target_fps| *target_fps > 0.0)
+            .and_then(|target_fps| Duration::try_from_secs_f32(1.0 / target_fps).ok());
+    }
+
+    pub fn get_target_fps(&self) -> Option<f32> {
+        self.frame_pacer
+            .target_frame_time
+            .map(|target_frame_time| 1.0 / target_frame_time.as_secs_f32())
+    }
+
+    /// the time it took to compose and write the last frame
+    pub fn get_frame_time(&self) -> Duration {
+        self.frame_pacer.frame_time
+    }
+
+    /// the time between the start of the last two frames
+    pub fn get_delta_time(&self) -> Duration {
+        self.frame_pacer.delta_time
     }
 
     pub fn set_scene_params(&mut self, scene_params: SceneParams) {
@@ -234,231 +563,578 @@ impl Renderer {
     }
 
     pub fn get_scene_params(&self) -> SceneParams {
-        self.scene_params
+        self.scene_params.clone()
     }
 
     ///  Renders the scene
+    /// waits for the target frame rate if one is set and adds a FrameTiming resource to the scene,
+    /// and the engine's DeltaTime resource if the scene doesn't have one
+    /// panics if rendering fails, use try_render to handle the error instead
     pub fn render(&mut self, scene: &mut EntitiesAndComponents) {
+        if let Err(error) = self.try_render(scene) {
+            panic!("{}", error);
+        }
+    }
+
+    /// Renders the scene, returns an error instead of panicking if there is no camera or the backend can't be written to
+    /// an error reading the input doesn't stop the frame, it is kept for get_input_error
+    pub fn try_render(&mut self, scene: &mut EntitiesAndComponents) -> Result<(), RenderError> {
+        self.frame_pacer.wait_for_next_frame();
+
+        {
+            let entities_and_components_ptr = scene as *mut EntitiesAndComponents;
+            // update the input resource
+            // SAFETY: the input resource does not access itself, so it safe.
+            match (unsafe { &mut *entities_and_components_ptr })
+                .get_resource_mut::<input::ConsoleInput>()
+            {
+                Some(input) => self.input_error = input.update(scene).err(),
+                None if self.input_error.is_none() => match input::ConsoleInput::new() {
+                    Ok(input) => scene.add_resource(input),
+                    Err(error) => self.input_error = Some(error),
+                },
+                // the input system failed to start, it isn't started again every frame
+                None => {}
+            }
+        }
+
         let scene_params;
         {
             scene_params = self.scene_params.clone();
         }
 
-        let mut pixel_grid =
-            vec![
-                vec![scene_params.background_color; self.renderer_params.width as usize];
-                self.renderer_params.height as usize
-            ];
+        self.try_render_to_grid_ref(scene)?;
+
+        // the frame is taken out so the renderer can be borrowed mutably while writing it
+        let pixel_grid = std::mem::take(&mut self.frame);
+        let result = ascii_renderer::try_render_pixel_grid(self, &pixel_grid, &scene_params);
+        self.frame = pixel_grid;
+        result?;
+        if let Some(frame_capture) = &mut self.frame_capture {
+            frame_capture.push(self.frame_pacer.frame_start(), &self.frame);
+        }
+        self.frame_pacer.end_frame();
+
+        self.update_timing_resources(scene);
+        Ok(())
+    }
+
+    /// why the input wasn't updated by the last call to render or try_render, None if it was
+    /// if the input system failed to start, it stays off and the scene is rendered without input
+    pub fn get_input_error(&self) -> Option<&RenderError> {
+        self.input_error.as_ref()
+    }
 
-        let camera_entities = scene
-            .get_entities_with_component::<Camera>()
-            .cloned()
-            .collect::<Vec<Entity>>();
-        if camera_entities.len() == 0 {
-            panic!("renderer could not find a camera");
+    /// publishes the FrameTiming of the last frame to the scene and advances DeltaTime, see render
+    fn update_timing_resources(&mut self, scene: &mut EntitiesAndComponents) {
+        let frame_timing = FrameTiming {
+            frame_time: self.frame_pacer.frame_time,
+        };
+        if let Some(timing) = scene.get_resource_mut::<FrameTiming>() {
+            *timing = frame_timing;
         } else {
-            // this will not panic if no active camera is found
-            for camera_entity in camera_entities {
-                let camera_component: Camera;
-                {
-                    let camera_component_ref = scene
-                        .try_get_component::<Camera>(camera_entity)
-                        .expect("renderer could not find a camera");
-                    camera_component = (&**camera_component_ref).clone();
-                }
+            scene.add_resource(frame_timing);
+        }
+        // a scene run by the engine's World already advances its DeltaTime every frame,
+        // so only a DeltaTime a renderer added is advanced here, the scene remembers which one it has
+        let added_by_renderer = scene.get_resource::<RendererDeltaTime>().is_some();
+        match scene.get_resource_mut::<DeltaTime>() {
+            Some(delta_time) if added_by_renderer => delta_time.update(),
+            Some(_) => {}
+            None => {
+                scene.add_resource(DeltaTime::new());
+                scene.add_resource(RendererDeltaTime);
+            }
+        }
+    }
 
-                if camera_component.is_active == true {
-                    if self.renderer_params.width != camera_component.width as u32
-                        || self.renderer_params.height != camera_component.height as u32
-                    {
-                        self.renderer_params.width = camera_component.width as u32;
-                        self.renderer_params.height = camera_component.height as u32;
-                        crossterm::queue!(
-                            self.handle,
-                            cursor::Hide,
-                            crossterm::terminal::SetSize(
-                                self.renderer_params.width as u16
-                                    * self.renderer_params.pixel_scale,
-                                self.renderer_params.height as u16
-                                    * self.renderer_params.pixel_scale
-                            ),
-                            crossterm::terminal::Clear(crossterm::terminal::ClearType::All),
-                        )
-                        .expect("Error: failed to set terminal size");
-
-                        pixel_grid = vec![
-                            vec![
-                                scene_params.background_color;
-                                self.renderer_params.width as usize
-                            ];
-                            self.renderer_params.height as usize
-                        ];
-                    }
+    /// Composes the scene into a frame without writing anything to the backend
+    /// runs the camera lookup, render_objects and compositing, useful for tests, simulations and tooling
+    /// panics if there is no camera, use try_render_to_grid to handle the error instead
+    pub fn render_to_grid(&mut self, scene: &mut EntitiesAndComponents) -> Frame {
+        self.render_to_grid_ref(scene).clone()
+    }
 
-                    let camera_transform = scene
-                        .try_get_components::<(Transform,)>(camera_entity)
-                        .0
-                        .expect("active camera does not have a transform!");
-
-                    let opposite_camera_transform = Transform {
-                        x: -camera_transform.x + self.renderer_params.width as f64 / 2.0,
-                        y: -camera_transform.y + self.renderer_params.height as f64 / 2.0,
-                        z: 0.0,
-                        rotation: -camera_transform.rotation,
-                        scale: 1.0 / camera_transform.scale,
-                        origin_x: 0.0,
-                        origin_y: 0.0,
-                    };
-
-                    self.render_objects(
-                        scene,
-                        &mut pixel_grid,
-                        opposite_camera_transform.clone(),
-                        &camera_component,
-                    );
-                    break;
+    /// Composes the scene into a frame, returns an error if there is no camera or the active camera has no transform
+    pub fn try_render_to_grid(
+        &mut self,
+        scene: &mut EntitiesAndComponents,
+    ) -> Result<Frame, RenderError> {
+        self.try_render_to_grid_ref(scene).cloned()
+    }
+
+    /// like render_to_grid, but borrows the frame instead of copying it
+    /// the frame is reused by the next call instead of being copied
+    pub fn render_to_grid_ref(&mut self, scene: &mut EntitiesAndComponents) -> &Frame {
+        match self.try_render_to_grid_ref(scene) {
+            Ok(frame) => frame,
+            Err(error) => panic!("{}", error),
+        }
+    }
+
+    /// like try_render_to_grid, but borrows the frame instead of copying it
+    pub fn try_render_to_grid_ref(
+        &mut self,
+        scene: &mut EntitiesAndComponents,
+    ) -> Result<&Frame, RenderError> {
+        self.frame_pacer.start_frame();
+        let background_color = self.scene_params.background_color;
+
+        if scene.get_entity_count_with_component::<Camera>() == 0 {
+            return Err(RenderError::NoCamera);
+        }
+
+        // the first active camera without a viewport fills the top left of the frame,
+        // every active camera with a viewport is drawn on top of it in order
+        let (cell_width, cell_height) = self.renderer_params.render_mode.pixels_per_cell();
+        let pixel_scale = self.renderer_params.pixel_scale as u32;
+        // a viewport that doesn't line up with the scaled pixels is rounded outwards,
+        // so it covers every cell it was given, the view drawn later covers the overlap
+        let cells_to_pixels = |start: u32, cells: u32, cell_size: u32| {
+            let first_pixel = start * cell_size / pixel_scale;
+            let end_pixel = ((start + cells) * cell_size).div_ceil(pixel_scale);
+            (first_pixel, (end_pixel - first_pixel).max(1))
+        };
+        let mut views = std::mem::take(&mut self.camera_views);
+        let mut texture_cameras = std::mem::take(&mut self.texture_cameras);
+        views.clear();
+        texture_cameras.clear();
+        let mut has_main_camera = false;
+        for &camera_entity in scene.get_entities_with_component::<Camera>() {
+            // a camera that can't be found is skipped instead of panicking
+            let Some(mut camera_component) = scene
+                .try_get_component::<Camera>(camera_entity)
+                .map(|camera| (**camera).clone())
+            else {
+                continue;
+            };
+
+            if !camera_component.is_active {
+                continue;
+            }
+            if let Some(render_target) = camera_component.render_target {
+                texture_cameras.push((camera_entity, camera_component, render_target));
+                continue;
+            }
+            match camera_component.viewport {
+                Some(viewport) => {
+                    let (x, width) = cells_to_pixels(viewport.x, viewport.width, cell_width);
+                    let (y, height) = cells_to_pixels(viewport.y, viewport.height, cell_height);
+                    camera_component.set_size(width, height);
+                    views.push((camera_entity, camera_component, x as usize, y as usize));
+                }
+                None if !has_main_camera => {
+                    has_main_camera = true;
+                    views.insert(0, (camera_entity, camera_component, 0, 0));
                 }
+                None => {}
             }
         }
 
-        ascii_renderer::render_pixel_grid(self, &pixel_grid, &scene_params);
+        // the frame has the size of the main camera and the other views are clipped to it,
+        // without a main camera it is big enough for every view
+        // it keeps its size if no active camera is found
+        if has_main_camera {
+            self.renderer_params.width = views[0].1.width;
+            self.renderer_params.height = views[0].1.height;
+        } else if !views.is_empty() {
+            let (width, height) = views.iter().fold((0, 0), |(width, height), view| {
+                let (_, camera, x, y) = view;
+                (
+                    width.max(*x as u32 + camera.width),
+                    height.max(*y as u32 + camera.height),
+                )
+            });
+            self.renderer_params.width = width;
+            self.renderer_params.height = height;
+        }
 
-        {
-            let entities_and_components_ptr = scene as *mut EntitiesAndComponents;
-            // update the input resource
-            // SAFETY: the input resource does not access itself, so it safe.
-            if let Some(input) = (unsafe { &mut *entities_and_components_ptr })
-                .get_resource_mut::<input::ConsoleInput>()
+        let mut pixel_grid = std::mem::take(&mut self.frame);
+        pixel_grid.reset(
+            self.renderer_params.width as usize,
+            self.renderer_params.height as usize,
+            background_color,
+        );
+
+        let mut result = Ok(());
+        let mut draw_buffers = std::mem::take(&mut self.draw_buffers);
+        let mut viewport_grid = std::mem::take(&mut self.viewport_grid);
+
+        // textures are drawn first so the screen sees them as they are this frame
+        texture_cameras.sort_by_key(|(_, camera_component, _)| camera_component.render_order);
+        for &(camera_entity, ref camera_component, render_target) in &texture_cameras {
+            if !scene.does_entity_exist(render_target) {
+                continue;
+            }
+            // only an Image is drawn into, a target with another sprite is left alone
+            if let Some(sprite) = scene.try_get_component::<Sprite>(render_target) {
+                if !matches!(**sprite, Sprite::Image(_)) {
+                    continue;
+                }
+            }
+            let Some(camera_transform) = scene
+                .try_get_components::<(Transform,)>(camera_entity)
+                .0
+                .cloned()
+            else {
+                result = Err(RenderError::CameraWithoutTransform);
+                break;
+            };
+
+            // a texture has square pixels, like a texture that was loaded from an image
+            viewport_grid.reset(
+                camera_component.width as usize,
+                camera_component.height as usize,
+                background_color,
+            );
+            self.render_objects(
+                scene,
+                &mut viewport_grid,
+                &mut draw_buffers,
+                opposite_camera_transform(&camera_transform, camera_component),
+                camera_component,
+                1.0,
+            );
+
+            // the old pixels of the texture are reused by the next camera
+            match scene
+                .try_get_component_mut::<Sprite>(render_target)
+                .map(|sprite| &mut **sprite)
             {
-                input.update(scene);
-            } else {
-                scene.add_resource(input::ConsoleInput::new());
+                Some(Sprite::Image(image)) => {
+                    std::mem::swap(&mut image.texture.pixels, &mut viewport_grid)
+                }
+                // the target has no sprite, any other sprite was skipped above
+                _ => scene.add_component_to(
+                    render_target,
+                    Sprite::Image(Image {
+                        texture: Texture {
+                            pixels: std::mem::take(&mut viewport_grid),
+                        },
+                    }),
+                ),
+            }
+        }
+
+        for &(camera_entity, ref camera_component, x, y) in &views {
+            if result.is_err() {
+                break;
+            }
+            let Some(camera_transform) = scene
+                .try_get_components::<(Transform,)>(camera_entity)
+                .0
+                .cloned()
+            else {
+                result = Err(RenderError::CameraWithoutTransform);
+                break;
+            };
+
+            let opposite_camera_transform =
+                opposite_camera_transform(&camera_transform, camera_component);
+
+            let (width, height) = (
+                camera_component.width as usize,
+                camera_component.height as usize,
+            );
+            if x >= pixel_grid.width() || y >= pixel_grid.height() {
+                continue;
+            }
+            // a view that covers the whole frame is drawn straight into it
+            if (x, y, width, height) == (0, 0, pixel_grid.width(), pixel_grid.height()) {
+                self.render_objects(
+                    scene,
+                    &mut pixel_grid,
+                    &mut draw_buffers,
+                    opposite_camera_transform,
+                    camera_component,
+                    self.pixel_stretch(),
+                );
+                continue;
+            }
+
+            viewport_grid.reset(width, height, background_color);
+            self.render_objects(
+                scene,
+                &mut viewport_grid,
+                &mut draw_buffers,
+                opposite_camera_transform,
+                camera_component,
+                self.pixel_stretch(),
+            );
+            let visible_width = width.min(pixel_grid.width() - x);
+            let visible_height = height.min(pixel_grid.height() - y);
+            for (row, viewport_row) in viewport_grid.rows().take(visible_height).enumerate() {
+                pixel_grid[y + row][x..x + visible_width]
+                    .copy_from_slice(&viewport_row[..visible_width]);
             }
         }
+        self.draw_buffers = draw_buffers;
+        self.viewport_grid = viewport_grid;
+        self.camera_views = views;
+        self.texture_cameras = texture_cameras;
+        if result.is_ok() {
+            self.apply_post_processes(&mut pixel_grid);
+        }
+
+        self.frame = pixel_grid;
+        self.frame_pacer.end_frame();
+        result.map(|_| &self.frame)
+    }
+
+    /// the number of horizontal bands a frame of the height is split into, each band is rasterized on its own thread
+    fn band_count(&self, height: usize) -> usize {
+        self.renderer_params
+            .render_threads
+            .min(height.div_ceil(MIN_BAND_HEIGHT))
+            .max(1)
     }
 
-    // not thread safe
     fn render_objects(
         &self,
         entities_and_components: &mut EntitiesAndComponents,
-        pixel_grid: &mut Vec<Vec<Color>>,
+        pixel_grid: &mut Frame,
+        draw_buffers: &mut DrawBuffers,
         camera_offset: Transform,
         camera: &Camera,
+        stretch: f32,
     ) {
-        let mut entity_depth_array = vec![];
+        let frame_start = self.frame_pacer.frame_start();
+        let DrawBuffers {
+            entity_depth_array,
+            entity_paths,
+            parent_entities,
+            visible_entities,
+            mask_grids,
+        } = draw_buffers;
+        entity_depth_array.clear();
+        entity_paths.clear();
+        parent_entities.clear();
 
         collect_renderable_entities(
-            &entities_and_components,
-            vec![],
+            entities_and_components,
+            parent_entities,
             &camera_offset,
-            &mut entity_depth_array,
+            entity_paths,
+            entity_depth_array,
         );
 
-        entity_depth_array.sort();
-
-        // could possibly be done multithreaded and combine layers afterward
-        for entity_depth_item in entity_depth_array {
-            let entities = entity_depth_item.entity;
+        // an unstable sort doesn't allocate, the order the entities were found in breaks ties instead
+        entity_depth_array.sort_unstable();
 
+        // animations are advanced first, so the sprites can be shared between the render threads afterwards
+        visible_entities.clear();
+        for entity_depth_item in entity_depth_array.iter() {
             let (current_entities_and_components, entity) =
-                get_entities_and_components_from_entity_list(entities_and_components, entities);
+                get_entities_and_components_from_entity_list(
+                    entities_and_components,
+                    &entity_paths[entity_depth_item.entity.clone()],
+                );
 
             let (sprite, mask, transform) = current_entities_and_components
                 .try_get_components_mut::<(Sprite, Mask, Transform)>(entity);
             {
                 // if the object doesn't have a sprite or transform, don't render it
-                match (sprite, mask, transform) {
+                let (sprite, transform) = match (sprite, mask, transform) {
                     (Some(sprite), None, Some(_)) => {
-                        let transform = &(entity_depth_item.transform);
+                        let transform = entity_depth_item.transform.clone();
 
-                        if !camera::object_is_in_view_of_camera(camera, transform, sprite) {
+                        if !camera::object_is_in_view_of_camera(camera, &transform, sprite) {
                             continue;
                         }
-
-                        // check if object is circle or rectangle
-                        match sprite {
-                            Sprite::Circle(circle) => shape_renderer::render_circle(
-                                &circle,
-                                &transform,
-                                pixel_grid,
-                                self.renderer_params.stretch,
-                            ),
-                            Sprite::Rectangle(rectangle) => shape_renderer::render_rectangle(
-                                &rectangle,
-                                &transform,
-                                pixel_grid,
-                                self.renderer_params.stretch,
-                            ),
-                            Sprite::Image(image) => shape_renderer::render_texture(
-                                &image.texture,
-                                &transform,
-                                pixel_grid,
-                                self.renderer_params.stretch,
-                            ),
-                            Sprite::Animation(animation) => {
-                                update_animation(animation);
-                                let current_frame = &animation.frames[animation.current_frame];
-                                shape_renderer::render_texture(
-                                    &current_frame.texture,
-                                    &transform,
-                                    pixel_grid,
-                                    self.renderer_params.stretch,
-                                );
-                            }
-                        }
+                        (sprite, transform)
                     }
-                    (Some(sprite), Some(mask), Some(transform)) => {
-                        let transform = &(&transform.clone() + &entity_depth_item.transform);
-                        // check if object is circle or rectangle
-                        match sprite {
-                            Sprite::Circle(circle) => shape_renderer::render_circle_with_mask(
-                                &circle,
-                                transform,
-                                pixel_grid,
-                                self.renderer_params.stretch,
-                                mask,
-                            ),
-                            Sprite::Rectangle(rectangle) => {
-                                shape_renderer::render_rectangle_with_mask(
-                                    &rectangle,
-                                    transform,
-                                    pixel_grid,
-                                    self.renderer_params.stretch,
-                                    mask,
-                                )
-                            }
-                            Sprite::Image(image) => shape_renderer::render_texture_with_mask(
-                                &image.texture,
-                                transform,
-                                pixel_grid,
-                                self.renderer_params.stretch,
-                                mask,
-                            ),
-                            Sprite::Animation(animation) => {
-                                update_animation(animation);
-                                let current_frame = &animation.frames[animation.current_frame];
-                                shape_renderer::render_texture(
-                                    &current_frame.texture,
-                                    transform,
-                                    pixel_grid,
-                                    self.renderer_params.stretch,
-                                );
-                            }
-                        }
+                    (Some(sprite), Some(_), Some(transform)) => {
+                        (sprite, &transform.clone() + &entity_depth_item.transform)
                     }
                     // can no longer render an object with a sprite but no transform
                     // because the transform is used as an offset
-                    (Some(_), None, None) => (),
-                    _ => (),
+                    (Some(_), None, None) => continue,
+                    _ => continue,
+                };
+
+                if let Sprite::Animation(animation) = sprite {
+                    update_animation(animation, frame_start);
                 }
+                visible_entities.push((entity_depth_item.entity.clone(), transform));
             }
         }
+
+        let entities_and_components = &*entities_and_components;
+        let entity_paths = &*entity_paths;
+        let draw_command = |(entities, transform): &(Range<usize>, Transform)| {
+            let (current_entities_and_components, entity) =
+                find_entities_and_components_from_entity_list(
+                    entities_and_components,
+                    &entity_paths[entities.clone()],
+                );
+            let (sprite, mask) =
+                current_entities_and_components.try_get_components::<(Sprite, Mask)>(entity);
+            DrawCommand {
+                sprite: sprite.expect("sprite was removed while rendering"),
+                mask,
+                transform: *transform,
+            }
+        };
+
+        // every band draws all of the sprites in depth order, so the result is the same as drawing the whole frame at once
+        let band_count = self.band_count(pixel_grid.height());
+        let band_height = pixel_grid.height().div_ceil(band_count).max(1);
+        if mask_grids.len() < band_count {
+            mask_grids.resize_with(band_count, FrameBuffer::default);
+        }
+        if band_count == 1 {
+            // the sprites are looked up while they are drawn, so no draw list is needed
+            render_band(
+                visible_entities.iter().map(draw_command),
+                pixel_grid.as_band_mut(0),
+                &mut mask_grids[0],
+                stretch,
+            );
+        } else {
+            // the scene can't be shared between threads, so the sprites are looked up into a draw list first
+            let draw_list = visible_entities
+                .iter()
+                .map(draw_command)
+                .collect::<Vec<DrawCommand>>();
+            let draw_list = &draw_list;
+            std::thread::scope(|scope| {
+                for (band, mask_grid) in
+                    pixel_grid.bands_mut(band_height).zip(mask_grids.iter_mut())
+                {
+                    scope.spawn(move || {
+                        render_band(draw_list.iter().copied(), band, mask_grid, stretch)
+                    });
+                }
+            });
+        }
     }
 }
 
-fn update_animation(animation: &mut Animation) {
-    if !animation.finished && animation.current_frame_start_time.elapsed() >= animation.frame_time {
-        animation.current_frame_start_time = Instant::now();
+/// the offset that moves the scene into the view of the camera, the camera's position ends up in the center of the view
+fn opposite_camera_transform(camera_transform: &Transform, camera: &Camera) -> Transform {
+    Transform {
+        x: -camera_transform.x + camera.width as f64 / 2.0,
+        y: -camera_transform.y + camera.height as f64 / 2.0,
+        z: 0.0,
+        rotation: -camera_transform.rotation,
+        scale: 1.0 / camera_transform.scale,
+        origin_x: 0.0,
+        origin_y: 0.0,
+    }
+}
+
+/// the fewest rows a render thread gets, smaller bands aren't worth the cost of a thread
+const MIN_BAND_HEIGHT: usize = 16;
+
+/// a sprite that is in view, ready to be rasterized
+#[derive(Clone, Copy)]
+struct DrawCommand<'a> {
+    sprite: &'a Sprite,
+    mask: Option<&'a Mask>,
+    transform: Transform,
+}
+
+/// the buffers render_objects fills for every camera, kept on the renderer so they are only allocated while they grow
+#[derive(Default)]
+struct DrawBuffers {
+    // the sprites of the scene, sorted by depth
+    entity_depth_array: Vec<EntityDepthItem>,
+    // the entity paths of the sprites one after another, the items hold ranges into it
+    entity_paths: Vec<Entity>,
+    // the path to the children that are being collected
+    parent_entities: Vec<Entity>,
+    // the entity paths and transforms of the sprites in view
+    visible_entities: Vec<(Range<usize>, Transform)>,
+    // one mask grid for each render thread
+    mask_grids: Vec<FrameBuffer<f32>>,
+}
+
+/// rasterizes every sprite of the draw list into a band of the frame, in order
+fn render_band<'a>(
+    draw_list: impl IntoIterator<Item = DrawCommand<'a>>,
+    mut pixel_grid: FrameBand<Color>,
+    mask_grid: &mut FrameBuffer<f32>,
+    stretch: f32,
+) {
+    let pixel_grid = &mut pixel_grid;
+    for draw_command in draw_list {
+        let transform = &draw_command.transform;
+        // check if object is circle or rectangle
+        match (draw_command.sprite, draw_command.mask) {
+            (Sprite::Circle(circle), None) => {
+                shape_renderer::render_circle(circle, transform, pixel_grid, stretch)
+            }
+            (Sprite::Rectangle(rectangle), None) => {
+                shape_renderer::render_rectangle(rectangle, transform, pixel_grid, stretch)
+            }
+            (Sprite::Image(image), None) => {
+                shape_renderer::render_texture(&image.texture, transform, pixel_grid, stretch)
+            }
+            (Sprite::Circle(circle), Some(mask)) => shape_renderer::render_circle_with_mask(
+                circle, transform, pixel_grid, stretch, mask, mask_grid,
+            ),
+            (Sprite::Rectangle(rectangle), Some(mask)) => {
+                shape_renderer::render_rectangle_with_mask(
+                    rectangle, transform, pixel_grid, stretch, mask, mask_grid,
+                )
+            }
+            (Sprite::Image(image), Some(mask)) => shape_renderer::render_texture_with_mask(
+                &image.texture,
+                transform,
+                pixel_grid,
+                stretch,
+                mask,
+                mask_grid,
+            ),
+            // masks are not applied to animations
+            (Sprite::Animation(animation), _) => {
+                let current_frame = &animation.frames[animation.current_frame];
+                shape_renderer::render_texture(
+                    &current_frame.texture,
+                    transform,
+                    pixel_grid,
+                    stretch,
+                );
+            }
+        }
+    }
+}
+
+impl Drop for Renderer {
+    /// shows the cursor and resets the colors so the terminal is usable after the renderer is gone
+    fn drop(&mut self) {
+        if std::thread::panicking() {
+            // the rest of a half written frame would end up on the normal screen, throw it away
+            let handle = std::mem::replace(
+                &mut self.handle,
+                std::io::BufWriter::new(BackendWriter {
+                    backend: Box::new(std::io::sink()),
+                    recorder: None,
+                }),
+            );
+            let (mut backend_writer, _) = handle.into_parts();
+            let _ = backend_writer.backend.write_all(b"\x1b[0m");
+            let _ = crossterm::queue!(backend_writer.backend, cursor::Show);
+            let _ = backend_writer.backend.flush();
+            if let Some(recorder) = backend_writer.recorder.take() {
+                let _ = recorder.finish();
+            }
+            return;
+        }
+        let _ = self.handle.write_all(b"\x1b[0m");
+        let _ = crossterm::queue!(self.handle, cursor::Show);
+        let _ = self.handle.flush();
+        let _ = self.stop_recording();
+    }
+}
+
+/// advances the animation if its frame has been shown for long enough at the start of this frame
+fn update_animation(animation: &mut Animation, frame_start: Instant) {
+    if !animation.finished
+        && frame_start.saturating_duration_since(animation.current_frame_start_time)
+            >= animation.frame_time
+    {
+        animation.current_frame_start_time = frame_start;
         animation.current_frame += 1;
         if animation.current_frame >= animation.frames.len() {
             if animation.loop_animation {
@@ -474,120 +1150,624 @@ fn update_animation(animation: &mut Animation) {
 fn collect_renderable_entities(
     entities_and_components: &EntitiesAndComponents,
     // the list of parent entities to get to the EntitiesAndComponents that is passed, starting with the root
-    parent_entities: Vec<Entity>,
+    parent_entities: &mut Vec<Entity>,
     transform_offset: &Transform,
+    // the paths of the collected entities are appended here, the items hold their ranges
+    entity_paths: &mut Vec<Entity>,
     out_list: &mut Vec<EntityDepthItem>,
 ) {
-    let entities_with_sprite = entities_and_components
-        .get_entities_with_component::<Sprite>()
-        .cloned()
-        .collect::<Vec<Entity>>();
-
-    for entity in entities_with_sprite {
+    for &entity in entities_and_components.get_entities_with_component::<Sprite>() {
         let (sprite, transform) =
             entities_and_components.try_get_components::<(Sprite, Transform)>(entity);
 
         match (sprite, transform) {
             (Some(_), Some(transform)) => {
-                let mut new_parents = parent_entities.clone();
-                new_parents.push(entity);
+                let path_start = entity_paths.len();
+                entity_paths.extend_from_slice(parent_entities);
+                entity_paths.push(entity);
                 out_list.push(EntityDepthItem {
-                    entity: new_parents,
+                    entity: path_start..entity_paths.len(),
                     transform: transform + transform_offset,
+                    order: out_list.len(),
                 });
             }
             _ => (),
         }
     }
 
-    let entities_with_children = entities_and_components
-        .get_entities_with_component::<EntitiesAndComponents>()
-        .cloned()
-        .collect::<Vec<Entity>>();
-
-    for entity in entities_with_children {
+    for &entity in entities_and_components.get_entities_with_component::<EntitiesAndComponents>() {
         let (transform, children) = entities_and_components
             .try_get_components::<(Transform, EntitiesAndComponents)>(entity);
 
-        match (transform, children) {
-            (Some(transform), Some(children)) => {
-                let mut new_parents = parent_entities.clone();
-                new_parents.push(entity);
-                collect_renderable_entities(
-                    children,
-                    new_parents,
-                    &(transform_offset + transform),
-                    out_list,
-                )
-            }
-            (None, Some(children)) => {
-                let mut new_parents = parent_entities.clone();
-                new_parents.push(entity);
-                collect_renderable_entities(children, new_parents, transform_offset, out_list)
-            }
-            _ => (),
+        let Some(children) = children else {
+            continue;
+        };
+        parent_entities.push(entity);
+        match transform {
+            Some(transform) => collect_renderable_entities(
+                children,
+                parent_entities,
+                &(transform_offset + transform),
+                entity_paths,
+                out_list,
+            ),
+            None => collect_renderable_entities(
+                children,
+                parent_entities,
+                transform_offset,
+                entity_paths,
+                out_list,
+            ),
         }
+        parent_entities.pop();
     }
 }
 
-/// takes a Vec<Entity> and returns the EntitiesAndComponents and Entity that it points to
-fn get_entities_and_components_from_entity_list(
-    entities_and_components: &mut EntitiesAndComponents,
-    mut entity_list: Vec<Entity>,
-) -> (&mut EntitiesAndComponents, Entity) {
-    if entity_list.len() == 0 {
-        panic!("entity list is empty, this should never happen, please report this as a bug");
-    }
-    if entity_list.len() == 1 {
-        return (entities_and_components, entity_list[0]);
-    }
-
-    let mut current_entities_and_components = entities_and_components;
-    let mut current_entity = entity_list[0];
+/// takes a list of entities and returns the EntitiesAndComponents and Entity that it points to
+fn get_entities_and_components_from_entity_list<'a>(
+    entities_and_components: &'a mut EntitiesAndComponents,
+    entity_list: &[Entity],
+) -> (&'a mut EntitiesAndComponents, Entity) {
     // the last entity in the list is the one we want to return, and it's not a parent so no need to check for children
-    let last_entity = entity_list.pop().unwrap();
+    let (last_entity, parent_entities) = entity_list
+        .split_last()
+        .expect("entity list is empty, this should never happen, please report this as a bug");
 
-    for entity in entity_list {
-        let children = current_entities_and_components
-            .try_get_components_mut::<(EntitiesAndComponents,)>(current_entity)
+    let mut current_entities_and_components = entities_and_components;
+    for parent_entity in parent_entities {
+        current_entities_and_components = current_entities_and_components
+            .try_get_components_mut::<(EntitiesAndComponents,)>(*parent_entity)
             .0
             .expect(
                 "failed to get children, this should never happen, please report this as a bug",
             );
+    }
+    (current_entities_and_components, *last_entity)
+}
 
-        current_entities_and_components = children;
-        current_entity = entity;
+/// same as get_entities_and_components_from_entity_list, but only borrows the scene immutably
+fn find_entities_and_components_from_entity_list<'a>(
+    entities_and_components: &'a EntitiesAndComponents,
+    entity_list: &[Entity],
+) -> (&'a EntitiesAndComponents, Entity) {
+    let (last_entity, parent_entities) = entity_list
+        .split_last()
+        .expect("entity list is empty, this should never happen, please report this as a bug");
+
+    let mut current_entities_and_components = entities_and_components;
+    for parent_entity in parent_entities {
+        current_entities_and_components = current_entities_and_components
+            .try_get_components::<(EntitiesAndComponents,)>(*parent_entity)
+            .0
+            .expect(
+                "failed to get children, this should never happen, please report this as a bug",
+            );
     }
-    (current_entities_and_components, last_entity)
+    (current_entities_and_components, *last_entity)
 }
 
 struct EntityDepthItem {
     /// ordered by child depth, so entity1 has entity2 as a child which has entity3 as a child
     /// entity1 will not be rendered as part of the pass for this object just entity3.
     /// entity1 and entity 2 will have its own pass
-    entity: Vec<Entity>,
+    /// the range of the path in DrawBuffers::entity_paths
+    entity: Range<usize>,
     transform: Transform,
+    /// the order the entity was found in, sprites at the same depth are drawn in this order
+    order: usize,
+}
+
+impl EntityDepthItem {
+    /// the depth used for sorting, a NaN is made positive so it sorts after every number whatever its sign
+    fn depth(&self) -> f64 {
+        let z = self.transform.z;
+        if z.is_nan() {
+            z.abs()
+        } else {
+            z
+        }
+    }
 }
 
 impl Eq for EntityDepthItem {}
 
 impl PartialEq for EntityDepthItem {
     fn eq(&self, other: &Self) -> bool {
-        self.transform.z == other.transform.z
+        self.cmp(other) == std::cmp::Ordering::Equal
     }
 }
 
 impl PartialOrd for EntityDepthItem {
     fn partial_cmp(&self, other: &Self) -> Option<std::cmp::Ordering> {
-        self.transform.z.partial_cmp(&other.transform.z)
+        Some(self.cmp(other))
     }
 }
 
 impl Ord for EntityDepthItem {
+    /// a NaN depth is drawn on top of everything instead of panicking
     fn cmp(&self, other: &Self) -> std::cmp::Ordering {
-        self.transform
-            .z
-            .partial_cmp(&other.transform.z)
-            .expect("failed to compare entity depth")
+        self.depth()
+            .total_cmp(&other.depth())
+            .then(self.order.cmp(&other.order))
+    }
+}
+
+#[cfg(test)]
+mod renderer_tests {
+    #[test]
+    fn render_to_grid_composes_without_writing() {
+        use crate::{camera::Camera, test_helpers::*, *};
+
+        let (mut renderer, backend) = memory_renderer();
+        renderer.set_stretch(1.0);
+        backend.take();
+
+        let mut scene = EntitiesAndComponents::new();
+        let camera = scene.add_entity();
+        scene.add_component_to(camera, Camera::new(8, 8));
+        scene.add_component_to(camera, Transform::default());
+
+        let square = scene.add_entity();
+        scene.add_component_to(
+            square,
+            Sprite::Rectangle(Rectangle {
+                width: 2.0,
+                height: 2.0,
+                color: RED,
+            }),
+        );
+        scene.add_component_to(square, Transform::default());
+
+        let frame = renderer.render_to_grid(&mut scene);
+        assert_eq!((frame.width(), frame.height()), (8, 8));
+        assert_eq!(frame[4][4], RED);
+        assert_eq!(frame[0][0], Color::default());
+        assert!(backend.take().is_empty());
+    }
+
+    #[test]
+    fn an_input_error_still_shows_the_frame() {
+        use crate::{camera::Camera, test_helpers::*, *};
+
+        let (mut renderer, backend) = memory_renderer();
+        let mut scene = EntitiesAndComponents::new();
+        let camera = scene.add_entity();
+        scene.add_component_to(camera, Camera::new(8, 8));
+        scene.add_component_to(camera, Transform::default());
+        let square = scene.add_entity();
+        scene.add_component_to(square, Transform::default());
+
+        // the scene has no Input resource, so updating the input fails from the second frame on
+        for color in [RED, BLUE] {
+            scene.add_component_to(
+                square,
+                Sprite::Rectangle(Rectangle {
+                    width: 2.0,
+                    height: 2.0,
+                    color,
+                }),
+            );
+            renderer.try_render(&mut scene).unwrap();
+            assert!(!backend.take().is_empty());
+        }
+        assert!(matches!(
+            renderer.get_input_error(),
+            Some(RenderError::MissingInputResource)
+        ));
+    }
+
+    #[test]
+    fn dropping_the_renderer_restores_the_cursor() {
+        use crate::{test_helpers::*, *};
+
+        let (renderer, backend) = memory_renderer();
+
+        drop(renderer);
+        assert_eq!(
+            String::from_utf8(backend.take()).unwrap(),
+            "\x1b[0m\x1b[?25h"
+        );
+
+        // a panic throws away the half written frame but still restores the cursor
+        let mut renderer = Renderer::with_backend(backend.clone());
+        renderer.handle.flush().unwrap();
+        backend.take();
+        let result = std::panic::catch_unwind(std::panic::AssertUnwindSafe(move || {
+            let _ = renderer.handle.write_all(b"half a frame");
+            panic!("the game crashed");
+        }));
+        assert!(result.is_err());
+        assert_eq!(
+            String::from_utf8(backend.take()).unwrap(),
+            "\x1b[0m\x1b[?25h"
+        );
+    }
+
+    #[test]
+    fn nan_depths_are_drawn_on_top() {
+        use crate::*;
+
+        let mut items = [f64::NAN, 1.0, -f64::NAN, f64::INFINITY, -2.0]
+            .into_iter()
+            .enumerate()
+            .map(|(order, z)| EntityDepthItem {
+                entity: 0..0,
+                transform: Transform {
+                    z,
+                    ..Default::default()
+                },
+                order,
+            })
+            .collect::<Vec<_>>();
+        items.sort();
+        let depths = items
+            .iter()
+            .map(|item| item.transform.z)
+            .collect::<Vec<_>>();
+        assert_eq!(depths[..3], [-2.0, 1.0, f64::INFINITY]);
+        assert!(depths[3..].iter().all(|z| z.is_nan()));
+    }
+
+    #[test]
+    fn target_fps_paces_frames() {
+        use crate::{camera::Camera, *};
+
+        let mut renderer = Renderer::headless();
+        renderer.set_target_fps(Some(1e-30));
+        assert_eq!(renderer.get_target_fps(), None);
+        renderer.set_target_fps(Some(50.0));
+        assert_eq!(renderer.get_target_fps(), Some(50.0));
+
+        let mut scene = EntitiesAndComponents::new();
+        let camera = scene.add_entity();
+        scene.add_component_to(camera, Camera::new(4, 4));
+        scene.add_component_to(camera, Transform::default());
+
+        // render_to_grid measures the frame but doesn't wait, only render does
+        renderer.render_to_grid(&mut scene);
+        renderer.frame_pacer.wait_for_next_frame();
+        renderer.render_to_grid(&mut scene);
+        // sleeps can overshoot by any amount on a busy machine, so only the lower bound is checked
+        assert!(renderer.get_delta_time() >= Duration::from_millis(15));
+    }
+
+    #[test]
+    fn only_a_delta_time_the_renderer_added_is_advanced() {
+        use crate::*;
+        use ABC_Game_Engine::DeltaTime;
+
+        let mut renderer = Renderer::headless();
+        let mut scene = EntitiesAndComponents::new();
+        renderer.update_timing_resources(&mut scene);
+        assert!(scene.get_resource::<FrameTiming>().is_some());
+        std::thread::sleep(Duration::from_millis(5));
+        renderer.update_timing_resources(&mut scene);
+        let delta_time = scene.get_resource::<DeltaTime>().unwrap();
+        assert!(delta_time.get_total_time() > 0.0);
+
+        // the engine advances the DeltaTime of its own scenes, even when the renderer added one to another scene
+        let mut scene = EntitiesAndComponents::new();
+        scene.add_resource(DeltaTime::new());
+        std::thread::sleep(Duration::from_millis(5));
+        renderer.update_timing_resources(&mut scene);
+        let delta_time = scene.get_resource::<DeltaTime>().unwrap();
+        assert_eq!(delta_time.get_total_time(), 0.0);
+    }
+
+    #[test]
+    fn banded_rasterization_matches_a_single_thread() {
+        use crate::{camera::Camera, mask::Mask, *};
+
+        let mut scene = EntitiesAndComponents::new();
+        let camera = scene.add_entity();
+        scene.add_component_to(camera, Camera::new(70, 50));
+        scene.add_component_to(camera, Transform::default());
+
+        for index in 0..20 {
+            let color = Color {
+                r: (index * 12) as u8,
+                g: 200 - (index * 9) as u8,
+                b: 90,
+                a: if index % 3 == 0 { 0.5 } else { 1.0 },
+            };
+            let sprite = if index % 2 == 0 {
+                Sprite::Circle(Circle {
+                    radius: 3.0 + index as f64 / 2.0,
+                    color,
+                })
+            } else {
+                Sprite::Rectangle(Rectangle {
+                    width: 12.0,
+                    height: 5.0 + index as f64,
+                    color,
+                })
+            };
+            let entity = scene.add_entity();
+            scene.add_component_to(entity, sprite);
+            scene.add_component_to(
+                entity,
+                Transform {
+                    x: index as f64 * 3.0 - 30.0,
+                    y: index as f64 * 2.0 - 20.0,
+                    z: index as f64,
+                    rotation: index as f64 * 0.3,
+                    ..Default::default()
+                },
+            );
+            if index % 5 == 0 {
+                let mask_shape = Circle {
+                    radius: 4.0,
+                    color: Color::default(),
+                };
+                scene.add_component_to(entity, Mask::new(mask_shape.into(), Transform::default()));
+            }
+        }
+
+        let mut renderer = Renderer::headless();
+        renderer.set_render_threads(1);
+        let single_threaded = renderer.render_to_grid(&mut scene);
+        assert!(single_threaded
+            .as_slice()
+            .iter()
+            .any(|pixel| *pixel != Color::default()));
+
+        renderer.set_render_threads(4);
+        assert_eq!(renderer.band_count(single_threaded.height()), 4);
+        assert_eq!(renderer.render_to_grid(&mut scene), single_threaded);
+    }
+
+    #[test]
+    fn cameras_draw_to_textures_in_render_order() {
+        use crate::{camera::Camera, test_helpers::*, *};
+
+        let mut scene = EntitiesAndComponents::new();
+        let square = scene.add_entity();
+        scene.add_component_to(
+            square,
+            Sprite::Rectangle(Rectangle {
+                width: 100.0,
+                height: 100.0,
+                color: RED,
+            }),
+        );
+        scene.add_component_to(
+            square,
+            Transform {
+                x: 1000.0,
+                ..Default::default()
+            },
+        );
+
+        // the monitor shows what the first camera sees of the screen that shows the square
+        let (screen, monitor) = (scene.add_entity(), scene.add_entity());
+        let mut add_camera = |x, width, render_target, render_order| {
+            let mut camera = Camera::new(width, width);
+            camera.set_render_target(render_target);
+            camera.set_render_order(render_order);
+            let entity = scene.add_entity();
+            scene.add_component_to(entity, camera);
+            scene.add_component_to(
+                entity,
+                Transform {
+                    x,
+                    ..Default::default()
+                },
+            );
+        };
+        add_camera(2000.0, 4, Some(monitor), 1);
+        add_camera(1000.0, 8, Some(screen), 0);
+        add_camera(0.0, 8, None, 0);
+        for (entity, x) in [(screen, 2000.0), (monitor, 0.0)] {
+            scene.add_component_to(
+                entity,
+                Transform {
+                    x,
+                    ..Default::default()
+                },
+            );
+        }
+
+        let mut renderer = Renderer::headless();
+        renderer.set_stretch(1.0);
+        let frame = renderer.render_to_grid(&mut scene);
+        assert_eq!((frame.width(), frame.height()), (8, 8));
+        assert_eq!(frame[4][4], RED);
+        assert_eq!(frame[0][0], Color::default());
+
+        let Sprite::Image(image) = &**scene.try_get_component::<Sprite>(monitor).unwrap() else {
+            panic!("the monitor should have an image sprite");
+        };
+        assert_eq!(image.texture.pixels, FrameBuffer::new(4, 4, RED));
+    }
+
+    #[test]
+    fn texture_cameras_leave_other_sprites_alone() {
+        use crate::{camera::Camera, test_helpers::*, *};
+
+        let mut scene = EntitiesAndComponents::new();
+        let camera = scene.add_entity();
+        scene.add_component_to(camera, Camera::new(8, 8));
+        scene.add_component_to(camera, Transform::default());
+
+        let target = scene.add_entity();
+        scene.add_component_to(
+            target,
+            Sprite::Circle(Circle {
+                radius: 2.0,
+                color: GREEN,
+            }),
+        );
+        scene.add_component_to(target, Transform::default());
+        let texture_camera = scene.add_entity();
+        let mut camera_component = Camera::new(4, 4);
+        camera_component.set_render_target(Some(target));
+        scene.add_component_to(texture_camera, camera_component);
+        scene.add_component_to(texture_camera, Transform::default());
+
+        let mut renderer = Renderer::headless();
+        renderer.render_to_grid(&mut scene);
+        assert!(matches!(
+            &**scene.try_get_component::<Sprite>(target).unwrap(),
+            Sprite::Circle(_)
+        ));
+    }
+
+    #[test]
+    fn viewports_are_rounded_outwards_with_a_pixel_scale() {
+        use crate::{
+            camera::{Camera, Viewport},
+            test_helpers::*,
+            *,
+        };
+
+        let colors = [RED, BLUE];
+        let mut scene = EntitiesAndComponents::new();
+        for (index, color) in colors.into_iter().enumerate() {
+            let x = index as f64 * 1000.0;
+            let square = scene.add_entity();
+            scene.add_component_to(
+                square,
+                Sprite::Rectangle(Rectangle {
+                    width: 100.0,
+                    height: 100.0,
+                    color,
+                }),
+            );
+            scene.add_component_to(
+                square,
+                Transform {
+                    x,
+                    ..Default::default()
+                },
+            );
+
+            let camera = scene.add_entity();
+            let mut camera_component = Camera::new(8, 4);
+            camera_component.set_viewport(Some(Viewport {
+                x: index as u32 * 3,
+                y: 0,
+                width: 3,
+                height: 2,
+            }));
+            scene.add_component_to(camera, camera_component);
+            scene.add_component_to(
+                camera,
+                Transform {
+                    x,
+                    ..Default::default()
+                },
+            );
+        }
+
+        // with a pixel scale of 2 every pixel is 2x2 cells, so 3 cells are 1.5 pixels
+        let mut renderer = Renderer::headless();
+        renderer.set_pixel_scale(2);
+        let frame = renderer.render_to_grid(&mut scene);
+        assert_eq!((frame.width(), frame.height()), (3, 1));
+        assert_eq!(frame[0], [colors[0], colors[1], colors[1]]);
+
+        renderer.set_pixel_scale(0);
+        let frame = renderer.render_to_grid(&mut scene);
+        assert_eq!(frame.height(), 2);
+    }
+
+    #[test]
+    fn cameras_with_viewports_share_the_frame() {
+        use crate::{
+            camera::{Camera, Viewport},
+            test_helpers::*,
+            *,
+        };
+
+        // two big squares far apart, each camera only sees one of them
+        let mut scene = EntitiesAndComponents::new();
+        let mut cameras = vec![];
+        for (x, color) in [(0.0, RED), (1000.0, BLUE)] {
+            let square = scene.add_entity();
+            scene.add_component_to(
+                square,
+                Sprite::Rectangle(Rectangle {
+                    width: 100.0,
+                    height: 100.0,
+                    color,
+                }),
+            );
+            scene.add_component_to(
+                square,
+                Transform {
+                    x,
+                    ..Default::default()
+                },
+            );
+
+            let camera = scene.add_entity();
+            scene.add_component_to(camera, Camera::new(8, 4));
+            scene.add_component_to(
+                camera,
+                Transform {
+                    x,
+                    ..Default::default()
+                },
+            );
+            cameras.push(camera);
+        }
+        let set_viewport = |scene: &mut EntitiesAndComponents, camera, viewport| {
+            scene
+                .get_components_mut::<(Camera,)>(camera)
+                .0
+                .set_viewport(viewport);
+        };
+
+        let mut renderer = Renderer::headless();
+        renderer.set_render_mode(RenderMode::HalfBlock);
+
+        // split screen, the viewports are in cells and a half block cell is two pixels tall
+        set_viewport(
+            &mut scene,
+            cameras[0],
+            Some(Viewport {
+                x: 0,
+                y: 0,
+                width: 3,
+                height: 2,
+            }),
+        );
+        set_viewport(
+            &mut scene,
+            cameras[1],
+            Some(Viewport {
+                x: 3,
+                y: 0,
+                width: 3,
+                height: 2,
+            }),
+        );
+        let frame = renderer.render_to_grid(&mut scene);
+        assert_eq!((frame.width(), frame.height()), (6, 4));
+        for row in frame.rows() {
+            assert_eq!(row, &[RED, RED, RED, BLUE, BLUE, BLUE]);
+        }
+
+        // picture in picture, the camera without a viewport fills the frame
+        set_viewport(&mut scene, cameras[0], None);
+        set_viewport(
+            &mut scene,
+            cameras[1],
+            Some(Viewport {
+                x: 6,
+                y: 1,
+                width: 4,
+                height: 2,
+            }),
+        );
+        // the picture reaches past the main camera, so it is clipped to it
+        let frame = renderer.render_to_grid(&mut scene);
+        assert_eq!((frame.width(), frame.height()), (8, 4));
+        for (y, row) in frame.rows().enumerate() {
+            for (x, pixel) in row.iter().enumerate() {
+                let in_picture = x >= 6 && y >= 2;
+                assert_eq!(*pixel, if in_picture { BLUE } else { RED });
+            }
+        }
     }
 }
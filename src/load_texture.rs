@@ -7,23 +7,30 @@ use std::{
 };
 
 /// Load a texture from a file, stretch
+/// panics if the image can't be loaded, use try_load_texture to handle the error instead
 pub fn load_texture(path: &str) -> Texture {
-    let image = image::open(&Path::new(path)).expect("Error: failed to open image");
+    match try_load_texture(path) {
+        Ok(texture) => texture,
+        Err(error) => panic!("{}", error),
+    }
+}
+
+/// Load a texture from a file, returns an error if the image can't be opened or decoded
+pub fn try_load_texture(path: &str) -> Result<Texture, RenderError> {
+    let image = image::open(&Path::new(path))?;
     let (width, height) = image.dimensions();
 
     let mut new_texture = Texture {
-        pixels: vec![
-            vec![
-                Color {
-                    r: 0,
-                    g: 0,
-                    b: 0,
-                    a: 0.0,
-                };
-                width as usize
-            ];
-            height as usize
-        ],
+        pixels: FrameBuffer::new(
+            width as usize,
+            height as usize,
+            Color {
+                r: 0,
+                g: 0,
+                b: 0,
+                a: 0.0,
+            },
+        ),
     };
 
     for x in 0..width {
@@ -38,22 +45,54 @@ pub fn load_texture(path: &str) -> Texture {
             new_texture.pixels[y as usize][x as usize] = color;
         }
     }
-    return new_texture;
+    Ok(new_texture)
 }
 
 /// Loads a spritesheet from a file
 /// The spritesheet must be a grid of cells
 /// Returns a vector of animations, one for each row of cells
+/// panics if the spritesheet can't be loaded, use try_load_spritesheet to handle the error instead
 pub fn load_spritesheet(
     number_of_cells_horizontal: u32,
     number_of_cells_vertical: u32,
     frame_duration_ms: u64,
     path: &str,
 ) -> Vec<Animation> {
+    match try_load_spritesheet(
+        number_of_cells_horizontal,
+        number_of_cells_vertical,
+        frame_duration_ms,
+        path,
+    ) {
+        Ok(spritesheets) => spritesheets,
+        Err(error) => panic!("{}", error),
+    }
+}
+
+/// Loads a spritesheet from a file, returns an error if the image can't be loaded
+/// or can't be split into the number of cells
+pub fn try_load_spritesheet(
+    number_of_cells_horizontal: u32,
+    number_of_cells_vertical: u32,
+    frame_duration_ms: u64,
+    path: &str,
+) -> Result<Vec<Animation>, RenderError> {
     let mut spritesheets = vec![];
-    let texture = load_texture(path);
+    let texture = try_load_texture(path)?;
     // split the texture into frames
-    let (texture_width, texture_height) = (texture.pixels[0].len(), texture.pixels.len());
+    let (texture_width, texture_height) = (texture.pixels.width(), texture.pixels.height());
+    if number_of_cells_horizontal == 0
+        || number_of_cells_vertical == 0
+        || number_of_cells_horizontal as usize > texture_width
+        || number_of_cells_vertical as usize > texture_height
+    {
+        return Err(RenderError::InvalidSpritesheet {
+            cells_horizontal: number_of_cells_horizontal,
+            cells_vertical: number_of_cells_vertical,
+            width: texture_width as u32,
+            height: texture_height as u32,
+        });
+    }
     let cell_width = texture_width as u32 / number_of_cells_horizontal;
     let cell_height = texture_height as u32 / number_of_cells_vertical;
 
@@ -69,18 +108,16 @@ pub fn load_spritesheet(
 
         for horizonal_cell_index in 0..number_of_cells_horizontal {
             let mut new_texture = Texture {
-                pixels: vec![
-                    vec![
-                        Color {
-                            r: 0,
-                            g: 0,
-                            b: 0,
-                            a: 0.0,
-                        };
-                        cell_width as usize
-                    ];
-                    cell_height as usize
-                ],
+                pixels: FrameBuffer::new(
+                    cell_width as usize,
+                    cell_height as usize,
+                    Color {
+                        r: 0,
+                        g: 0,
+                        b: 0,
+                        a: 0.0,
+                    },
+                ),
             };
             for x in 0..cell_width {
                 for y in 0..cell_height {
@@ -96,5 +133,5 @@ pub fn load_spritesheet(
         spritesheets.push(spritesheet);
     }
 
-    return spritesheets;
+    Ok(spritesheets)
 }
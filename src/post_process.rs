@@ -0,0 +1,445 @@
+use crate::*;
+use std::time::Duration;
+
+/// A pass that changes the composed frame before it is written, added with Renderer::add_post_process
+/// closures that take the frame and the time work as passes too
+pub trait PostProcess {
+    /// changes the frame in place, time is how long the renderer has been rendering, for animated effects
+    fn apply(&mut self, frame: &mut Frame, time: Duration);
+}
+
+impl<F: FnMut(&mut Frame, Duration)> PostProcess for F {
+    fn apply(&mut self, frame: &mut Frame, time: Duration) {
+        self(frame, time)
+    }
+}
+
+impl Renderer {
+    /// adds a pass that runs on every composed frame, passes run in the order they were added
+    /// the passes run in render_to_grid, so screenshots and captures include them
+    pub fn add_post_process(&mut self, post_process: impl PostProcess + 'static) {
+        self.post_processes.push(Box::new(post_process));
+    }
+
+    /// removes every post process pass
+    pub fn clear_post_processes(&mut self) {
+        self.post_processes.clear();
+    }
+
+    /// runs every pass on the frame
+    pub(crate) fn apply_post_processes(&mut self, frame: &mut Frame) {
+        let time = self.frame_pacer.elapsed();
+        for post_process in &mut self.post_processes {
+            post_process.apply(frame, time);
+        }
+    }
+}
+
+/// Darkens the frame towards its corners
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Vignette {
+    /// how dark the corners get, 0 is no change and 1 is black
+    pub strength: f32,
+    /// how far from the center the darkening starts, 0 is the center and 1 is the middle of the edges
+    pub radius: f32,
+}
+
+impl Vignette {
+    pub fn new(strength: f32, radius: f32) -> Vignette {
+        Vignette { strength, radius }
+    }
+}
+
+impl PostProcess for Vignette {
+    fn apply(&mut self, frame: &mut Frame, _: Duration) {
+        let (half_width, half_height) = (frame.width() as f32 / 2.0, frame.height() as f32 / 2.0);
+        // the distance to the corners
+        let corner = std::f32::consts::SQRT_2;
+        let falloff = (corner - self.radius).max(f32::EPSILON);
+        for (y, row) in frame.rows_mut().enumerate() {
+            for (x, pixel) in row.iter_mut().enumerate() {
+                let dx = (x as f32 + 0.5 - half_width) / half_width;
+                let dy = (y as f32 + 0.5 - half_height) / half_height;
+                let distance = (dx * dx + dy * dy).sqrt();
+                let darkness = ((distance - self.radius) / falloff).clamp(0.0, 1.0);
+                *pixel = scale_color(*pixel, 1.0 - self.strength * darkness);
+            }
+        }
+    }
+}
+
+/// Darkens every few rows like the scanlines of an old monitor
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Scanlines {
+    /// how dark the lines are, 0 is no change and 1 is black
+    pub intensity: f32,
+    /// the number of rows from one line to the next, 2 darkens every other row
+    pub spacing: usize,
+    /// how many rows per second the lines scroll down, 0 keeps them still
+    pub speed: f32,
+}
+
+impl Scanlines {
+    pub fn new(intensity: f32, spacing: usize) -> Scanlines {
+        Scanlines {
+            intensity,
+            spacing,
+            speed: 0.0,
+        }
+    }
+
+    pub fn with_speed(mut self, speed: f32) -> Self {
+        self.speed = speed;
+        self
+    }
+}
+
+impl PostProcess for Scanlines {
+    fn apply(&mut self, frame: &mut Frame, time: Duration) {
+        let spacing = self.spacing.max(1);
+        let offset = (time.as_secs_f32() * self.speed) as usize % spacing;
+        for (y, row) in frame.rows_mut().enumerate() {
+            if (y + spacing - offset) % spacing == spacing - 1 {
+                for pixel in row {
+                    *pixel = scale_color(*pixel, 1.0 - self.intensity);
+                }
+            }
+        }
+    }
+}
+
+/// Bends the frame like the curved glass of an old monitor, the corners are filled with the border color
+#[derive(Clone, Debug, PartialEq)]
+pub struct CrtCurvature {
+    /// how much the frame is bent, 0 is flat
+    pub amount: f32,
+    pub border_color: Color,
+    // a copy of the frame that is read while the frame is written
+    source: Frame,
+}
+
+impl CrtCurvature {
+    pub fn new(amount: f32) -> CrtCurvature {
+        CrtCurvature {
+            amount,
+            border_color: Color::default(),
+            source: Frame::default(),
+        }
+    }
+
+    pub fn with_border_color(mut self, border_color: Color) -> Self {
+        self.border_color = border_color;
+        self
+    }
+}
+
+impl PostProcess for CrtCurvature {
+    fn apply(&mut self, frame: &mut Frame, _: Duration) {
+        self.source.clone_from(frame);
+        let (width, height) = (frame.width() as f32, frame.height() as f32);
+        for (y, row) in frame.rows_mut().enumerate() {
+            for (x, pixel) in row.iter_mut().enumerate() {
+                // -1 to 1 from edge to edge
+                let u = (x as f32 + 0.5) / width * 2.0 - 1.0;
+                let v = (y as f32 + 0.5) / height * 2.0 - 1.0;
+                let bend = 1.0 + self.amount * (u * u + v * v);
+                let source_x = ((u * bend + 1.0) / 2.0 * width).floor();
+                let source_y = ((v * bend + 1.0) / 2.0 * height).floor();
+
+                *pixel = if (0.0..width).contains(&source_x) && (0.0..height).contains(&source_y) {
+                    self.source[source_y as usize][source_x as usize]
+                } else {
+                    self.border_color
+                };
+            }
+        }
+    }
+}
+
+/// Changes the brightness, contrast, saturation and tint of the frame
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct ColorGrading {
+    /// added to every channel, -1 is black and 1 is white
+    pub brightness: f32,
+    /// 1 is no change, less is grayer and more is harsher
+    pub contrast: f32,
+    /// 1 is no change, 0 is grayscale
+    pub saturation: f32,
+    /// every channel is multiplied by the tint, white is no change
+    pub tint: Color,
+}
+
+impl ColorGrading {
+    pub fn new() -> ColorGrading {
+        ColorGrading {
+            brightness: 0.0,
+            contrast: 1.0,
+            saturation: 1.0,
+            tint: Color {
+                r: 255,
+                g: 255,
+                b: 255,
+                a: 1.0,
+            },
+        }
+    }
+
+    pub fn with_brightness(mut self, brightness: f32) -> Self {
+        self.brightness = brightness;
+        self
+    }
+
+    pub fn with_contrast(mut self, contrast: f32) -> Self {
+        self.contrast = contrast;
+        self
+    }
+
+    pub fn with_saturation(mut self, saturation: f32) -> Self {
+        self.saturation = saturation;
+        self
+    }
+
+    pub fn with_tint(mut self, tint: Color) -> Self {
+        self.tint = tint;
+        self
+    }
+}
+
+impl Default for ColorGrading {
+    fn default() -> Self {
+        ColorGrading::new()
+    }
+}
+
+impl PostProcess for ColorGrading {
+    fn apply(&mut self, frame: &mut Frame, _: Duration) {
+        for pixel in frame.as_mut_slice() {
+            let channels = [pixel.r, pixel.g, pixel.b].map(|channel| channel as f32 / 255.0);
+            let luminance = 0.2126 * channels[0] + 0.7152 * channels[1] + 0.0722 * channels[2];
+            let tint = [self.tint.r, self.tint.g, self.tint.b];
+            let [r, g, b] = std::array::from_fn(|i| {
+                let channel = luminance + (channels[i] - luminance) * self.saturation;
+                let channel = (channel - 0.5) * self.contrast + 0.5 + self.brightness;
+                let channel = channel * tint[i] as f32 / 255.0;
+                (channel.clamp(0.0, 1.0) * 255.0).round() as u8
+            });
+            *pixel = Color { r, g, b, ..*pixel };
+        }
+    }
+}
+
+/// Blurs the frame with a box blur, the alpha of every pixel is kept
+#[derive(Clone, Debug, PartialEq)]
+pub struct Blur {
+    /// the number of pixels on each side that are averaged, 0 is no blur
+    pub radius: usize,
+    // the frame after the horizontal pass
+    horizontal: Frame,
+}
+
+impl Blur {
+    pub fn new(radius: usize) -> Blur {
+        Blur {
+            radius,
+            horizontal: Frame::default(),
+        }
+    }
+}
+
+impl PostProcess for Blur {
+    fn apply(&mut self, frame: &mut Frame, _: Duration) {
+        if self.radius == 0 || frame.is_empty() {
+            return;
+        }
+        let (width, height) = (frame.width(), frame.height());
+        self.horizontal.clone_from(frame);
+        for y in 0..height {
+            for x in 0..width {
+                let columns = x.saturating_sub(self.radius)..(x + self.radius + 1).min(width);
+                let average = average_rgb(columns.map(|x| frame[y][x]));
+                self.horizontal[y][x] = Color {
+                    a: frame[y][x].a,
+                    ..average
+                };
+            }
+        }
+        for y in 0..height {
+            let rows = y.saturating_sub(self.radius)..(y + self.radius + 1).min(height);
+            for x in 0..width {
+                let average = average_rgb(rows.clone().map(|y| self.horizontal[y][x]));
+                frame[y][x] = Color {
+                    a: frame[y][x].a,
+                    ..average
+                };
+            }
+        }
+    }
+}
+
+/// Inverts the colors of the frame
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct Invert;
+
+impl PostProcess for Invert {
+    fn apply(&mut self, frame: &mut Frame, _: Duration) {
+        for pixel in frame.as_mut_slice() {
+            pixel.r = 255 - pixel.r;
+            pixel.g = 255 - pixel.g;
+            pixel.b = 255 - pixel.b;
+        }
+    }
+}
+
+/// multiplies the color channels by the factor, the alpha is kept
+fn scale_color(color: Color, factor: f32) -> Color {
+    let scale = |channel: u8| (channel as f32 * factor.clamp(0.0, 1.0)).round() as u8;
+    Color {
+        r: scale(color.r),
+        g: scale(color.g),
+        b: scale(color.b),
+        a: color.a,
+    }
+}
+
+/// the average color of the pixels, the alpha is always 1
+fn average_rgb(pixels: impl Iterator<Item = Color>) -> Color {
+    let (mut r, mut g, mut b, mut count) = (0u32, 0u32, 0u32, 0u32);
+    for pixel in pixels {
+        r += pixel.r as u32;
+        g += pixel.g as u32;
+        b += pixel.b as u32;
+        count += 1;
+    }
+    let count = count.max(1);
+    Color {
+        r: ((r + count / 2) / count) as u8,
+        g: ((g + count / 2) / count) as u8,
+        b: ((b + count / 2) / count) as u8,
+        a: 1.0,
+    }
+}
+
+#[cfg(test)]
+mod post_process_tests {
+    #[test]
+    fn passes_run_in_order_on_the_composed_frame() {
+        use crate::{camera::Camera, test_helpers::*, *};
+        use std::time::Duration;
+
+        let mut scene = EntitiesAndComponents::new();
+        let camera = scene.add_entity();
+        scene.add_component_to(camera, Camera::new(4, 4));
+        scene.add_component_to(camera, Transform::default());
+
+        let mut renderer = Renderer::headless();
+        renderer.add_post_process(Invert);
+        renderer.add_post_process(|frame: &mut Frame, _: Duration| frame[0][0].r = 0);
+        let frame = renderer.render_to_grid(&mut scene);
+        assert_eq!(frame[0][0], Color { r: 0, ..WHITE });
+        assert_eq!(frame[3][3], WHITE);
+
+        renderer.clear_post_processes();
+        assert_eq!(renderer.render_to_grid(&mut scene)[0][0], Color::default());
+    }
+
+    #[test]
+    fn vignette_darkens_the_edges() {
+        use crate::{test_helpers::*, *};
+        use std::time::Duration;
+
+        let mut frame = FrameBuffer::new(8, 8, GRAY);
+        Vignette::new(1.0, 0.5).apply(&mut frame, Duration::ZERO);
+        assert_eq!(frame[4][4], GRAY);
+        assert!(frame[0][0].r < 50);
+    }
+
+    #[test]
+    fn scanlines_darken_every_other_row() {
+        use crate::{test_helpers::*, *};
+        use std::time::Duration;
+
+        let mut frame = FrameBuffer::new(2, 4, GRAY);
+        Scanlines::new(0.5, 2).apply(&mut frame, Duration::ZERO);
+        let shades = frame.rows().map(|row| row[0].r).collect::<Vec<_>>();
+        assert_eq!(shades, [200, 100, 200, 100]);
+    }
+
+    #[test]
+    fn scanlines_scroll_with_their_speed() {
+        use crate::{test_helpers::*, *};
+        use std::time::Duration;
+
+        // at one row per second the lines moved down a row after a second
+        let mut frame = FrameBuffer::new(2, 4, GRAY);
+        Scanlines::new(0.5, 2)
+            .with_speed(1.0)
+            .apply(&mut frame, Duration::from_secs(1));
+        let shades = frame.rows().map(|row| row[0].r).collect::<Vec<_>>();
+        assert_eq!(shades, [100, 200, 100, 200]);
+    }
+
+    #[test]
+    fn crt_curvature_bends_the_corners_away() {
+        use crate::{test_helpers::*, *};
+        use std::time::Duration;
+
+        let mut frame = FrameBuffer::new(8, 8, GRAY);
+        CrtCurvature::new(0.5).apply(&mut frame, Duration::ZERO);
+        assert_eq!(frame[4][4], GRAY);
+        assert_eq!(frame[0][0], Color::default());
+    }
+
+    #[test]
+    fn color_grading_desaturates_and_tints() {
+        use crate::{test_helpers::*, *};
+        use std::time::Duration;
+
+        let mut frame = FrameBuffer::new(1, 1, GRAY);
+        ColorGrading::new()
+            .with_saturation(0.0)
+            .with_tint(Color {
+                r: 255,
+                g: 0,
+                b: 255,
+                a: 1.0,
+            })
+            .apply(&mut frame, Duration::ZERO);
+        assert_eq!((frame[0][0].r, frame[0][0].g, frame[0][0].b), (200, 0, 200));
+    }
+
+    #[test]
+    fn blur_spreads_a_pixel_over_its_neighbours() {
+        use crate::*;
+        use std::time::Duration;
+
+        let mut frame = FrameBuffer::new(5, 5, Color::default());
+        frame[2][2] = Color {
+            r: 225,
+            g: 225,
+            b: 225,
+            a: 1.0,
+        };
+        Blur::new(1).apply(&mut frame, Duration::ZERO);
+        assert_eq!(frame[1][1].r, 25);
+        assert_eq!(frame[2][2].r, 25);
+        assert_eq!(frame[0][0].r, 0);
+    }
+
+    #[test]
+    fn invert_flips_every_channel() {
+        use crate::{test_helpers::*, *};
+        use std::time::Duration;
+
+        let mut frame = FrameBuffer::new(1, 1, GRAY);
+        Invert.apply(&mut frame, Duration::ZERO);
+        assert_eq!(
+            frame[0][0],
+            Color {
+                r: 55,
+                g: 55,
+                b: 55,
+                a: 1.0,
+            }
+        );
+    }
+}
@@ -0,0 +1,210 @@
+use crate::cast_recorder::CastRecorder;
+use std::io::{IsTerminal, Write};
+use std::sync::{Arc, Mutex};
+
+/// An output target that the renderer writes its frames to
+/// implemented for stdout, stderr, files (including ptys and /dev/tty) and sockets,
+/// use a MemoryBackend to read the output back
+pub trait RenderBackend: Write {
+    /// the size of the terminal in cells (columns, rows)
+    /// None if the backend is not a terminal, then the frame is never clipped
+    fn terminal_size(&self) -> Option<(u16, u16)> {
+        None
+    }
+
+    /// true if the terminal shows frames wrapped in synchronized updates (DEC mode 2026) atomically
+    fn supports_synchronized_output(&self) -> bool {
+        false
+    }
+}
+
+/// the size of the controlling terminal if the output is a terminal
+fn tty_size(is_terminal: bool) -> Option<(u16, u16)> {
+    if !is_terminal {
+        return None;
+    }
+    crossterm::terminal::size()
+        .ok()
+        .filter(|(columns, rows)| *columns > 0 && *rows > 0)
+}
+
+/// guesses from the TERM, TERM_PROGRAM and terminal specific environment variables
+/// if the terminal supports synchronized updates, it checks a list of known terminals instead of asking the terminal,
+/// because the answer would race with the input handling
+/// CONSOLE_RENDERER_SYNCHRONIZED_OUTPUT=1 or 0 overrides the guess
+fn synchronized_output_from_env() -> bool {
+    let var = |name| std::env::var(name).unwrap_or_default();
+    match var("CONSOLE_RENDERER_SYNCHRONIZED_OUTPUT").as_str() {
+        "1" => return true,
+        "0" => return false,
+        _ => (),
+    }
+    let (term, term_program) = (var("TERM"), var("TERM_PROGRAM"));
+
+    [
+        "kitty",
+        "foot",
+        "alacritty",
+        "wezterm",
+        "contour",
+        "ghostty",
+    ]
+    .iter()
+    .any(|name| term.contains(name))
+        || [
+            "WezTerm",
+            "iTerm.app",
+            "vscode",
+            "ghostty",
+            "contour",
+            "tmux",
+        ]
+        .contains(&term_program.as_str())
+        || !var("KITTY_WINDOW_ID").is_empty()
+        || !var("WT_SESSION").is_empty()
+}
+
+impl RenderBackend for std::io::Stdout {
+    fn terminal_size(&self) -> Option<(u16, u16)> {
+        tty_size(self.is_terminal())
+    }
+
+    fn supports_synchronized_output(&self) -> bool {
+        self.is_terminal() && synchronized_output_from_env()
+    }
+}
+
+impl RenderBackend for std::io::StdoutLock<'static> {
+    fn terminal_size(&self) -> Option<(u16, u16)> {
+        tty_size(self.is_terminal())
+    }
+
+    fn supports_synchronized_output(&self) -> bool {
+        self.is_terminal() && synchronized_output_from_env()
+    }
+}
+
+impl RenderBackend for std::io::Stderr {
+    fn terminal_size(&self) -> Option<(u16, u16)> {
+        tty_size(self.is_terminal())
+    }
+
+    fn supports_synchronized_output(&self) -> bool {
+        self.is_terminal() && synchronized_output_from_env()
+    }
+}
+
+impl RenderBackend for std::fs::File {}
+
+impl RenderBackend for std::net::TcpStream {}
+
+#[cfg(unix)]
+impl RenderBackend for std::os::unix::net::UnixStream {}
+
+impl RenderBackend for std::io::Sink {}
+
+/// the writer behind the renderer's buffer
+/// passes everything to the backend, and to the recorder while a recording is running
+pub(crate) struct BackendWriter {
+    pub(crate) backend: Box<dyn RenderBackend>,
+    pub(crate) recorder: Option<CastRecorder>,
+}
+
+impl Write for BackendWriter {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        let written = self.backend.write(buf)?;
+        if let Some(recorder) = &mut self.recorder {
+            recorder.record(&buf[..written]);
+        }
+        Ok(written)
+    }
+
+    /// the renderer flushes once a frame, so a flush is when the frame is presented
+    fn flush(&mut self) -> std::io::Result<()> {
+        self.backend.flush()?;
+        if let Some(recorder) = &mut self.recorder {
+            recorder.present();
+        }
+        Ok(())
+    }
+}
+
+/// An in-memory backend, every clone shares the same buffer
+/// useful for capturing frames in tests without a real terminal
+#[derive(Clone, Default)]
+pub struct MemoryBackend {
+    buffer: Arc<Mutex<Vec<u8>>>,
+    terminal_size: Arc<Mutex<Option<(u16, u16)>>>,
+}
+
+impl MemoryBackend {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// returns a copy of everything written so far
+    pub fn contents(&self) -> Vec<u8> {
+        self.buffer
+            .lock()
+            .expect("memory backend was poisoned")
+            .clone()
+    }
+
+    /// returns everything written so far and clears the buffer
+    pub fn take(&self) -> Vec<u8> {
+        std::mem::take(&mut *self.buffer.lock().expect("memory backend was poisoned"))
+    }
+
+    /// pretends to be a terminal of the given size (columns, rows), useful for testing resizes
+    pub fn set_terminal_size(&self, terminal_size: Option<(u16, u16)>) {
+        *self
+            .terminal_size
+            .lock()
+            .expect("memory backend was poisoned") = terminal_size;
+    }
+}
+
+impl Write for MemoryBackend {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        self.buffer
+            .lock()
+            .expect("memory backend was poisoned")
+            .extend_from_slice(buf);
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        Ok(())
+    }
+}
+
+impl RenderBackend for MemoryBackend {
+    fn terminal_size(&self) -> Option<(u16, u16)> {
+        *self
+            .terminal_size
+            .lock()
+            .expect("memory backend was poisoned")
+    }
+}
+
+#[cfg(test)]
+mod render_backend_tests {
+    #[test]
+    fn frames_are_captured_by_memory_backend() {
+        use crate::{test_helpers::*, *};
+
+        let (mut renderer, backend) = memory_renderer();
+
+        let pixel_grid = FrameBuffer::from(vec![vec![RED; 4]; 4]);
+        let scene_params = renderer.get_scene_params();
+
+        ascii_renderer::render_pixel_grid(&mut renderer, &pixel_grid, &scene_params);
+        let frame = String::from_utf8(backend.take()).unwrap();
+        assert_eq!(frame.matches('=').count(), 16);
+        assert_eq!(frame.matches("\x1b[38;2;255;0;0m").count(), 1);
+
+        // nothing changed so nothing should be written
+        ascii_renderer::render_pixel_grid(&mut renderer, &pixel_grid, &scene_params);
+        assert!(backend.take().is_empty());
+    }
+}
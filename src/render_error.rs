@@ -0,0 +1,141 @@
+use std::fmt;
+
+/// An error returned by the fallible (try_) versions of the renderer and texture loading functions
+#[derive(Debug)]
+pub enum RenderError {
+    /// the scene has no entity with a Camera component
+    NoCamera,
+    /// the active camera has no Transform component
+    CameraWithoutTransform,
+    /// writing to the backend failed, for example because the pipe or socket was closed
+    Io(std::io::Error),
+    /// an image could not be opened or decoded
+    Image(image::ImageError),
+    /// the spritesheet can't be split into the requested number of cells
+    InvalidSpritesheet {
+        cells_horizontal: u32,
+        cells_vertical: u32,
+        width: u32,
+        height: u32,
+    },
+    /// the scene has no Input resource for the console input to update
+    MissingInputResource,
+    /// the input system could not be started
+    InputUnavailable(String),
+    /// a capture was saved while nothing is being captured or before a frame was captured
+    NothingCaptured,
+}
+
+impl fmt::Display for RenderError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            RenderError::NoCamera => write!(f, "renderer could not find a camera"),
+            RenderError::CameraWithoutTransform => {
+                write!(f, "active camera does not have a transform")
+            }
+            RenderError::Io(error) => write!(f, "failed to write to the render backend: {}", error),
+            RenderError::Image(error) => write!(f, "failed to load image: {}", error),
+            RenderError::InvalidSpritesheet {
+                cells_horizontal,
+                cells_vertical,
+                width,
+                height,
+            } => write!(
+                f,
+                "a {}x{} image can't be split into {}x{} spritesheet cells",
+                width, height, cells_horizontal, cells_vertical
+            ),
+            RenderError::MissingInputResource => {
+                write!(
+                    f,
+                    "failed to get input resource from entities and components"
+                )
+            }
+            RenderError::InputUnavailable(reason) => {
+                write!(f, "failed to start the input system: {}", reason)
+            }
+            RenderError::NothingCaptured => write!(f, "there are no captured frames to save"),
+        }
+    }
+}
+
+impl std::error::Error for RenderError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            RenderError::Io(error) => Some(error),
+            RenderError::Image(error) => Some(error),
+            _ => None,
+        }
+    }
+}
+
+impl From<std::io::Error> for RenderError {
+    fn from(error: std::io::Error) -> Self {
+        RenderError::Io(error)
+    }
+}
+
+impl From<image::ImageError> for RenderError {
+    fn from(error: image::ImageError) -> Self {
+        RenderError::Image(error)
+    }
+}
+
+#[cfg(test)]
+mod render_error_tests {
+    #[test]
+    fn missing_camera_and_bad_assets_are_errors() {
+        use crate::{camera::Camera, *};
+
+        let mut renderer = Renderer::headless();
+        let mut scene = EntitiesAndComponents::new();
+        assert!(matches!(
+            renderer.try_render_to_grid(&mut scene),
+            Err(RenderError::NoCamera)
+        ));
+
+        let camera = scene.add_entity();
+        scene.add_component_to(camera, Camera::new(4, 4));
+        assert!(matches!(
+            renderer.try_render_to_grid(&mut scene),
+            Err(RenderError::CameraWithoutTransform)
+        ));
+
+        assert!(matches!(
+            try_load_texture("this/image/does/not/exist.png"),
+            Err(RenderError::Image(_))
+        ));
+    }
+
+    #[test]
+    fn write_errors_are_returned() {
+        use crate::*;
+        use std::io::{self, Write};
+
+        struct ClosedPipe;
+
+        impl Write for ClosedPipe {
+            fn write(&mut self, _: &[u8]) -> io::Result<usize> {
+                Err(io::ErrorKind::BrokenPipe.into())
+            }
+
+            fn flush(&mut self) -> io::Result<()> {
+                Err(io::ErrorKind::BrokenPipe.into())
+            }
+        }
+
+        impl RenderBackend for ClosedPipe {}
+
+        let mut renderer = Renderer::with_backend(ClosedPipe);
+        let pixel_grid = FrameBuffer::new(4, 4, Color::default());
+        let scene_params = renderer.get_scene_params();
+        let result =
+            ascii_renderer::try_render_pixel_grid(&mut renderer, &pixel_grid, &scene_params);
+        match result {
+            Err(RenderError::Io(error)) => assert_eq!(error.kind(), io::ErrorKind::BrokenPipe),
+            _ => panic!("expected a broken pipe"),
+        }
+        // it is unknown what reached the terminal, so the next frame is a full redraw
+        assert!(renderer.last_pixel_grid.is_empty());
+    }
+}
@@ -0,0 +1,115 @@
+use crate::*;
+use std::path::Path;
+
+/// How a frame is turned into an image
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum ScreenshotMode {
+    /// one image pixel for every pixel of the frame
+    Pixels,
+    /// every pixel becomes a block scale pixels wide and scale * stretch pixels tall,
+    /// so the image has the same proportions as the frame in the terminal
+    TerminalAspect { scale: u32 },
+}
+
+impl Renderer {
+    /// turns the last composed frame into an image
+    pub fn screenshot(&self, mode: ScreenshotMode) -> image::RgbaImage {
+        let (pixel_width, pixel_height) = self.screenshot_pixel_size(mode);
+        frame_to_image(&self.frame, pixel_width, pixel_height)
+    }
+
+    /// saves the last composed frame as a png, see render and render_to_grid
+    pub fn save_screenshot(
+        &self,
+        path: impl AsRef<Path>,
+        mode: ScreenshotMode,
+    ) -> Result<(), RenderError> {
+        self.screenshot(mode)
+            .save_with_format(path, image::ImageFormat::Png)?;
+        Ok(())
+    }
+
+    /// the size of the block of image pixels that one pixel of the frame becomes
+    pub(crate) fn screenshot_pixel_size(&self, mode: ScreenshotMode) -> (u32, u32) {
+        match mode {
+            ScreenshotMode::Pixels => (1, 1),
+            ScreenshotMode::TerminalAspect { scale } => {
+                let scale = scale.max(1);
+                let pixel_height = (scale as f32 * self.pixel_stretch()).round().max(1.0);
+                (scale, pixel_height as u32)
+            }
+        }
+    }
+}
+
+/// draws every pixel of the frame as a block of pixel_width by pixel_height image pixels
+pub(crate) fn frame_to_image(
+    frame: &Frame,
+    pixel_width: u32,
+    pixel_height: u32,
+) -> image::RgbaImage {
+    image::RgbaImage::from_fn(
+        frame.width() as u32 * pixel_width,
+        frame.height() as u32 * pixel_height,
+        |x, y| {
+            let pixel = frame[(y / pixel_height) as usize][(x / pixel_width) as usize];
+            image::Rgba([
+                pixel.r,
+                pixel.g,
+                pixel.b,
+                (pixel.a.clamp(0.0, 1.0) * 255.0).round() as u8,
+            ])
+        },
+    )
+}
+
+#[cfg(test)]
+mod screenshot_tests {
+    #[test]
+    fn screenshots_keep_the_pixels_or_the_terminal_aspect() {
+        use crate::{camera::Camera, test_helpers::*, *};
+
+        let mut renderer = Renderer::headless();
+        renderer.set_stretch(2.0);
+
+        let mut scene = EntitiesAndComponents::new();
+        let camera = scene.add_entity();
+        scene.add_component_to(camera, Camera::new(6, 4));
+        scene.add_component_to(camera, Transform::default());
+        let square = scene.add_entity();
+        scene.add_component_to(
+            square,
+            Sprite::Rectangle(Rectangle {
+                width: 1.0,
+                height: 1.0,
+                color: RED,
+            }),
+        );
+        scene.add_component_to(square, Transform::default());
+        let frame = renderer.render_to_grid(&mut scene);
+
+        let rgba = |pixel: Color| [pixel.r, pixel.g, pixel.b, 255];
+        assert!(frame.as_slice().contains(&RED));
+
+        let image = renderer.screenshot(ScreenshotMode::Pixels);
+        assert_eq!(image.dimensions(), (6, 4));
+        for (x, y, pixel) in image.enumerate_pixels() {
+            assert_eq!(pixel.0, rgba(frame[y as usize][x as usize]));
+        }
+
+        // stretch 2 in character mode makes every pixel twice as tall as it is wide
+        let image = renderer.screenshot(ScreenshotMode::TerminalAspect { scale: 3 });
+        assert_eq!(image.dimensions(), (18, 24));
+        for (x, y, pixel) in image.enumerate_pixels() {
+            assert_eq!(pixel.0, rgba(frame[y as usize / 6][x as usize / 3]));
+        }
+
+        let path = std::env::temp_dir().join("console_renderer_screenshot_test.png");
+        renderer
+            .save_screenshot(&path, ScreenshotMode::Pixels)
+            .unwrap();
+        let saved = image::open(&path).unwrap().to_rgba8();
+        std::fs::remove_file(&path).unwrap();
+        assert_eq!(saved, renderer.screenshot(ScreenshotMode::Pixels));
+    }
+}
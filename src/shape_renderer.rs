@@ -1,30 +1,30 @@
 use crate::mask::{Mask, MaskShape};
 use crate::*;
+use std::ops::Range;
 // should the shape structs be moved to this file?
 
-/// turns a Mask into a mask grid
+/// turns a Mask into a mask grid covering the columns and rows a sprite can be drawn on, reusing the mask grid's allocation
+/// the mask grid is indexed relative to the first of those columns and rows
 fn render_mask(
     mask: &Mask,
     transform: &Transform,
-    pixel_grid: &mut Vec<Vec<Color>>,
+    columns: &Range<usize>,
+    rows: &Range<usize>,
+    mask_grid: &mut FrameBuffer<f32>,
     stretch: f32,
-) -> Vec<Vec<f32>> {
+) {
+    mask_grid.reset(columns.len(), rows.len(), 1.0);
+    let transform = &(transform + &mask.transform);
     match &mask.shape {
         MaskShape::Circle(circle) => {
-            render_circle_mask(circle, &(transform + &mask.transform), pixel_grid, stretch)
+            render_circle_mask(circle, transform, columns, rows, mask_grid, stretch)
+        }
+        MaskShape::Rectangle(rectangle) => {
+            render_rectangle_mask(rectangle, transform, columns, rows, mask_grid, stretch)
+        }
+        MaskShape::Image(image) => {
+            render_texture_mask(&image.texture, transform, columns, rows, mask_grid, stretch)
         }
-        MaskShape::Rectangle(rectangle) => render_rectangle_mask(
-            rectangle,
-            &(transform + &mask.transform),
-            pixel_grid,
-            stretch,
-        ),
-        MaskShape::Image(image) => render_texture_mask(
-            &image.texture,
-            &(transform + &mask.transform),
-            pixel_grid,
-            stretch,
-        ),
     }
 }
 
@@ -32,94 +32,85 @@ fn render_mask(
 fn render_circle_mask(
     circle: &Circle,
     transform: &Transform,
-    pixel_grid: &mut Vec<Vec<Color>>,
+    columns: &Range<usize>,
+    rows: &Range<usize>,
+    mask_grid: &mut FrameBuffer<f32>,
     stretch: f32,
-) -> Vec<Vec<f32>> {
-    let mut mask_grid = vec![vec![1.0; pixel_grid[0].len()]; pixel_grid.len()];
-    for x in 0..pixel_grid[0].len() {
-        for y in 0..pixel_grid.len() {
-            let mut adjusted_x = (x as f32 / stretch) / transform.scale;
-            let mut adjusted_y = y as f32 / transform.scale;
-
-            if transform.rotation != 0.0 {
-                (adjusted_x, adjusted_y) = rotate_point_around(
-                    adjusted_x,
-                    adjusted_y,
-                    transform.x as f32 + transform.origin_x,
-                    transform.y as f32 + transform.origin_y,
-                    transform.rotation,
-                );
-            }
+) {
+    let sprite_space = SpriteSpace::shape(transform, stretch);
+    let (mask_columns, mask_rows) = sprite_space.screen_bounds(
+        transform,
+        circle.radius,
+        circle.radius,
+        columns.clone(),
+        rows.clone(),
+    );
+    for y in mask_rows {
+        for x in mask_columns.clone() {
+            let (adjusted_x, adjusted_y) = sprite_space.to_sprite(x, y);
 
             let dx = adjusted_x as f64 - transform.x;
             let dy = adjusted_y as f64 - transform.y;
 
             let distance_squared = dx.powi(2) + dy.powi(2);
             if distance_squared <= circle.radius.powi(2) {
-                mask_grid[y][x] = circle.color.a;
+                mask_grid[y - rows.start][x - columns.start] = circle.color.a;
             }
         }
     }
-    mask_grid
 }
 
 fn render_rectangle_mask(
     rectangle: &Rectangle,
     transform: &Transform,
-    pixel_grid: &mut Vec<Vec<Color>>,
+    columns: &Range<usize>,
+    rows: &Range<usize>,
+    mask_grid: &mut FrameBuffer<f32>,
     stretch: f32,
-) -> Vec<Vec<f32>> {
-    let mut mask_grid = vec![vec![1.0; pixel_grid[0].len()]; pixel_grid.len()];
-
-    for x in 0..pixel_grid[0].len() {
-        for y in 0..pixel_grid.len() {
-            let mut adjusted_x = (x as f32 / stretch) / transform.scale;
-            let mut adjusted_y = y as f32 / transform.scale;
-
-            if transform.rotation != 0.0 {
-                (adjusted_x, adjusted_y) = rotate_point_around(
-                    adjusted_x,
-                    adjusted_y,
-                    transform.x as f32 + transform.origin_x,
-                    transform.y as f32 + transform.origin_y,
-                    transform.rotation,
-                );
-            }
+) {
+    let sprite_space = SpriteSpace::shape(transform, stretch);
+    let (mask_columns, mask_rows) = sprite_space.screen_bounds(
+        transform,
+        rectangle.width / 2.0,
+        rectangle.height / 2.0,
+        columns.clone(),
+        rows.clone(),
+    );
+    for y in mask_rows {
+        for x in mask_columns.clone() {
+            let (adjusted_x, adjusted_y) = sprite_space.to_sprite(x, y);
 
             if adjusted_x as f64 >= transform.x - rectangle.width / 2.0
                 && adjusted_x as f64 <= transform.x + rectangle.width / 2.0
                 && adjusted_y as f64 >= transform.y - rectangle.height / 2.0
                 && adjusted_y as f64 <= transform.y + rectangle.height / 2.0
             {
-                mask_grid[y][x] = rectangle.color.a;
+                mask_grid[y - rows.start][x - columns.start] = rectangle.color.a;
             }
         }
     }
-    mask_grid
 }
 
 fn render_texture_mask(
     texture: &Texture,
     transform: &Transform,
-    pixel_grid: &mut Vec<Vec<Color>>,
+    columns: &Range<usize>,
+    rows: &Range<usize>,
+    mask_grid: &mut FrameBuffer<f32>,
     stretch: f32,
-) -> Vec<Vec<f32>> {
-    let (texture_width, texture_height) = (texture.pixels[0].len(), texture.pixels.len());
-    let mut mask_grid = vec![vec![1.0; pixel_grid[0].len()]; pixel_grid.len()];
-    for x in 0..pixel_grid[0].len() {
-        for y in 0..pixel_grid.len() {
-            let mut adjusted_x = x as f32 / transform.scale;
-            let mut adjusted_y = (y as f32 * stretch) / transform.scale;
-
-            if transform.rotation != 0.0 {
-                (adjusted_x, adjusted_y) = rotate_point_around(
-                    adjusted_x,
-                    adjusted_y,
-                    transform.x as f32 + transform.origin_x,
-                    transform.y as f32 + transform.origin_y,
-                    transform.rotation,
-                );
-            }
+) {
+    let (texture_width, texture_height) = (texture.pixels.width(), texture.pixels.height());
+    let sprite_space = SpriteSpace::texture(transform, stretch);
+    let (mask_columns, mask_rows) = sprite_space.screen_bounds(
+        transform,
+        texture_width as f64 / 2.0,
+        texture_height as f64 / 2.0,
+        columns.clone(),
+        rows.clone(),
+    );
+    for y in mask_rows {
+        for x in mask_columns.clone() {
+            let (adjusted_x, adjusted_y) = sprite_space.to_sprite(x, y);
 
             //positions relative to the center of the texture where the origin is centered
             let relative_x = adjusted_x as f64 - transform.x;
@@ -137,17 +128,17 @@ fn render_texture_mask(
                     continue;
                 }
 
-                mask_grid[y][x] = texture.pixels[texture_y_coord][texture_x_coord].a;
+                mask_grid[y - rows.start][x - columns.start] =
+                    texture.pixels[texture_y_coord][texture_x_coord].a;
             }
         }
     }
-    mask_grid
 }
 
 pub(crate) fn render_circle(
     circle: &Circle,
     transform: &Transform,
-    pixel_grid: &mut Vec<Vec<Color>>,
+    pixel_grid: &mut FrameBand<Color>,
     stretch: f32,
 ) {
     if circle.color.a == 0.0 {
@@ -155,20 +146,17 @@ pub(crate) fn render_circle(
     }
 
     let squared_radius = circle.radius.powi(2);
-    for x in 0..pixel_grid[0].len() {
-        for y in 0..pixel_grid.len() {
-            let mut adjusted_x = (x as f32 / stretch) / transform.scale;
-            let mut adjusted_y = y as f32 / transform.scale;
-
-            if transform.rotation != 0.0 {
-                (adjusted_x, adjusted_y) = rotate_point_around(
-                    adjusted_x,
-                    adjusted_y,
-                    transform.x as f32 + transform.origin_x,
-                    transform.y as f32 + transform.origin_y,
-                    transform.rotation,
-                );
-            }
+    let sprite_space = SpriteSpace::shape(transform, stretch);
+    let (columns, rows) = sprite_space.screen_bounds(
+        transform,
+        circle.radius,
+        circle.radius,
+        0..pixel_grid.width(),
+        pixel_grid.rows(),
+    );
+    for y in rows {
+        for x in columns.clone() {
+            let (adjusted_x, adjusted_y) = sprite_space.to_sprite(x, y);
 
             let dx = adjusted_x as f64 - transform.x;
             let dy = adjusted_y as f64 - transform.y;
@@ -190,26 +178,23 @@ pub(crate) fn render_circle(
 pub(crate) fn render_rectangle(
     rectangle: &Rectangle,
     transform: &Transform,
-    pixel_grid: &mut Vec<Vec<Color>>,
+    pixel_grid: &mut FrameBand<Color>,
     stretch: f32,
 ) {
     if rectangle.color.a == 0.0 {
         return;
     }
-    for x in 0..pixel_grid[0].len() {
-        for y in 0..pixel_grid.len() {
-            let mut adjusted_x = (x as f32 / stretch) / transform.scale;
-            let mut adjusted_y = y as f32 / transform.scale;
-
-            if transform.rotation != 0.0 {
-                (adjusted_x, adjusted_y) = rotate_point_around(
-                    adjusted_x,
-                    adjusted_y,
-                    transform.x as f32 + transform.origin_x,
-                    transform.y as f32 + transform.origin_y,
-                    transform.rotation,
-                );
-            }
+    let sprite_space = SpriteSpace::shape(transform, stretch);
+    let (columns, rows) = sprite_space.screen_bounds(
+        transform,
+        rectangle.width / 2.0,
+        rectangle.height / 2.0,
+        0..pixel_grid.width(),
+        pixel_grid.rows(),
+    );
+    for y in rows {
+        for x in columns.clone() {
+            let (adjusted_x, adjusted_y) = sprite_space.to_sprite(x, y);
 
             if adjusted_x as f64 >= transform.x - rectangle.width / 2.0
                 && adjusted_x as f64 <= transform.x + rectangle.width / 2.0
@@ -231,24 +216,21 @@ pub(crate) fn render_rectangle(
 pub(crate) fn render_texture(
     texture: &Texture,
     transform: &Transform,
-    pixel_grid: &mut Vec<Vec<Color>>,
+    pixel_grid: &mut FrameBand<Color>,
     stretch: f32,
 ) {
-    let (texture_width, texture_height) = (texture.pixels[0].len(), texture.pixels.len());
-    for x in 0..pixel_grid[0].len() {
-        for y in 0..pixel_grid.len() {
-            let mut adjusted_x = x as f32 / transform.scale;
-            let mut adjusted_y = (y as f32 * stretch) / transform.scale;
-
-            if transform.rotation != 0.0 {
-                (adjusted_x, adjusted_y) = rotate_point_around(
-                    adjusted_x,
-                    adjusted_y,
-                    transform.x as f32 + transform.origin_x,
-                    transform.y as f32 + transform.origin_y,
-                    transform.rotation,
-                );
-            }
+    let (texture_width, texture_height) = (texture.pixels.width(), texture.pixels.height());
+    let sprite_space = SpriteSpace::texture(transform, stretch);
+    let (columns, rows) = sprite_space.screen_bounds(
+        transform,
+        texture_width as f64 / 2.0,
+        texture_height as f64 / 2.0,
+        0..pixel_grid.width(),
+        pixel_grid.rows(),
+    );
+    for y in rows {
+        for x in columns.clone() {
+            let (adjusted_x, adjusted_y) = sprite_space.to_sprite(x, y);
 
             //positions relative to the center of the texture where the origin is centered
             let relative_x = adjusted_x as f64 - transform.x;
@@ -284,31 +266,28 @@ pub(crate) fn render_texture(
 pub(crate) fn render_circle_with_mask(
     circle: &Circle,
     transform: &Transform,
-    pixel_grid: &mut Vec<Vec<Color>>,
+    pixel_grid: &mut FrameBand<Color>,
     stretch: f32,
     mask: &Mask,
+    mask_grid: &mut FrameBuffer<f32>,
 ) {
-    let mask_grid = render_mask(mask, transform, pixel_grid, stretch);
-
     if circle.color.a == 0.0 {
         return;
     }
 
     let squared_radius = circle.radius.powi(2);
-    for x in 0..pixel_grid[0].len() {
-        for y in 0..pixel_grid.len() {
-            let mut adjusted_x = (x as f32 / stretch) / transform.scale;
-            let mut adjusted_y = y as f32 / transform.scale;
-
-            if transform.rotation != 0.0 {
-                (adjusted_x, adjusted_y) = rotate_point_around(
-                    adjusted_x,
-                    adjusted_y,
-                    transform.x as f32 + transform.origin_x,
-                    transform.y as f32 + transform.origin_y,
-                    transform.rotation,
-                );
-            }
+    let sprite_space = SpriteSpace::shape(transform, stretch);
+    let (columns, rows) = sprite_space.screen_bounds(
+        transform,
+        circle.radius,
+        circle.radius,
+        0..pixel_grid.width(),
+        pixel_grid.rows(),
+    );
+    render_mask(mask, transform, &columns, &rows, mask_grid, stretch);
+    for y in rows.clone() {
+        for x in columns.clone() {
+            let (adjusted_x, adjusted_y) = sprite_space.to_sprite(x, y);
 
             let dx = adjusted_x as f64 - transform.x;
             let dy = adjusted_y as f64 - transform.y;
@@ -317,7 +296,7 @@ pub(crate) fn render_circle_with_mask(
             if distance_squared <= squared_radius {
                 let pixel = &mut pixel_grid[y][x];
                 let mut new_pixel = circle.color.clone();
-                new_pixel.a *= mask_grid[y][x];
+                new_pixel.a *= mask_grid[y - rows.start][x - columns.start];
 
                 if circle.color.a == 1.0 {
                     *pixel = new_pixel;
@@ -333,29 +312,26 @@ pub(crate) fn render_circle_with_mask(
 pub(crate) fn render_rectangle_with_mask(
     rectangle: &Rectangle,
     transform: &Transform,
-    pixel_grid: &mut Vec<Vec<Color>>,
+    pixel_grid: &mut FrameBand<Color>,
     stretch: f32,
     mask: &Mask,
+    mask_grid: &mut FrameBuffer<f32>,
 ) {
-    let mask_grid = render_mask(&mask, transform, pixel_grid, stretch);
-
     if rectangle.color.a == 0.0 {
         return;
     }
-    for x in 0..pixel_grid[0].len() {
-        for y in 0..pixel_grid.len() {
-            let mut adjusted_x = (x as f32 / stretch) / transform.scale;
-            let mut adjusted_y = y as f32 / transform.scale;
-
-            if transform.rotation != 0.0 {
-                (adjusted_x, adjusted_y) = rotate_point_around(
-                    adjusted_x,
-                    adjusted_y,
-                    transform.x as f32 + transform.origin_x,
-                    transform.y as f32 + transform.origin_y,
-                    transform.rotation,
-                );
-            }
+    let sprite_space = SpriteSpace::shape(transform, stretch);
+    let (columns, rows) = sprite_space.screen_bounds(
+        transform,
+        rectangle.width / 2.0,
+        rectangle.height / 2.0,
+        0..pixel_grid.width(),
+        pixel_grid.rows(),
+    );
+    render_mask(mask, transform, &columns, &rows, mask_grid, stretch);
+    for y in rows.clone() {
+        for x in columns.clone() {
+            let (adjusted_x, adjusted_y) = sprite_space.to_sprite(x, y);
 
             if adjusted_x as f64 >= transform.x - rectangle.width / 2.0
                 && adjusted_x as f64 <= transform.x + rectangle.width / 2.0
@@ -365,7 +341,7 @@ pub(crate) fn render_rectangle_with_mask(
                 let pixel = &mut pixel_grid[y][x];
 
                 let mut new_pixel = rectangle.color.clone();
-                new_pixel.a *= mask_grid[y][x];
+                new_pixel.a *= mask_grid[y - rows.start][x - columns.start];
 
                 if new_pixel.a == 1.0 {
                     *pixel = new_pixel;
@@ -381,26 +357,24 @@ pub(crate) fn render_rectangle_with_mask(
 pub(crate) fn render_texture_with_mask(
     texture: &Texture,
     transform: &Transform,
-    pixel_grid: &mut Vec<Vec<Color>>,
+    pixel_grid: &mut FrameBand<Color>,
     stretch: f32,
     mask: &Mask,
+    mask_grid: &mut FrameBuffer<f32>,
 ) {
-    let mask_grid = render_mask(mask, transform, pixel_grid, stretch);
-    let (texture_width, texture_height) = (texture.pixels[0].len(), texture.pixels.len());
-    for x in 0..pixel_grid[0].len() {
-        for y in 0..pixel_grid.len() {
-            let mut adjusted_x = x as f32 / transform.scale;
-            let mut adjusted_y = (y as f32 * stretch) / transform.scale;
-
-            if transform.rotation != 0.0 {
-                (adjusted_x, adjusted_y) = rotate_point_around(
-                    adjusted_x,
-                    adjusted_y,
-                    transform.x as f32 + transform.origin_x,
-                    transform.y as f32 + transform.origin_y,
-                    transform.rotation,
-                );
-            }
+    let (texture_width, texture_height) = (texture.pixels.width(), texture.pixels.height());
+    let sprite_space = SpriteSpace::texture(transform, stretch);
+    let (columns, rows) = sprite_space.screen_bounds(
+        transform,
+        texture_width as f64 / 2.0,
+        texture_height as f64 / 2.0,
+        0..pixel_grid.width(),
+        pixel_grid.rows(),
+    );
+    render_mask(mask, transform, &columns, &rows, mask_grid, stretch);
+    for y in rows.clone() {
+        for x in columns.clone() {
+            let (adjusted_x, adjusted_y) = sprite_space.to_sprite(x, y);
 
             //positions relative to the center of the texture where the origin is centered
             let relative_x = adjusted_x as f64 - transform.x;
@@ -422,7 +396,7 @@ pub(crate) fn render_texture_with_mask(
 
                 let texture_pixel = &texture.pixels[texture_y_coord][texture_x_coord];
                 let mut texture_pixel = texture_pixel.clone();
-                texture_pixel.a *= mask_grid[y][x];
+                texture_pixel.a *= mask_grid[y - rows.start][x - columns.start];
 
                 if texture_pixel.a == 1.0 {
                     *out_pixel = texture_pixel;
@@ -452,23 +426,191 @@ fn overlay_colors(back_color: &Color, front_color: &Color) -> Color {
     return_color
 }
 
-fn rotate_point_around(x: f32, y: f32, cx: f32, cy: f32, angle_degrees: f64) -> (f32, f32) {
-    // Convert angle from degrees to radians
-    let angle_radians = angle_degrees.to_radians();
+/// maps pixels of the grid into the space of a sprite
+/// the sin and cos of the rotation are computed once per sprite instead of once per pixel
+struct SpriteSpace {
+    scale: f32,
+    stretch: f32,
+    // shapes divide x by the stretch, textures multiply y by it
+    stretch_x: bool,
+    origin_x: f32,
+    origin_y: f32,
+    // (sin, cos), None if the sprite is not rotated
+    rotation: Option<(f32, f32)>,
+}
 
-    // Translate the point to the origin
-    let translated_x = x - cx;
-    let translated_y = y - cy;
+impl SpriteSpace {
+    fn shape(transform: &Transform, stretch: f32) -> SpriteSpace {
+        SpriteSpace::new(transform, stretch, true)
+    }
+
+    fn texture(transform: &Transform, stretch: f32) -> SpriteSpace {
+        SpriteSpace::new(transform, stretch, false)
+    }
 
-    // Perform the rotation using trigonometric functions
-    let rotated_x =
-        translated_x * angle_radians.cos() as f32 - translated_y * angle_radians.sin() as f32;
-    let rotated_y =
-        translated_x * angle_radians.sin() as f32 + translated_y * angle_radians.cos() as f32;
+    fn new(transform: &Transform, stretch: f32, stretch_x: bool) -> SpriteSpace {
+        let angle_radians = transform.rotation.to_radians();
+        SpriteSpace {
+            scale: transform.scale,
+            stretch,
+            stretch_x,
+            origin_x: transform.x as f32 + transform.origin_x,
+            origin_y: transform.y as f32 + transform.origin_y,
+            rotation: (transform.rotation != 0.0)
+                .then(|| (angle_radians.sin() as f32, angle_radians.cos() as f32)),
+        }
+    }
 
-    // Translate the point back to its original position
-    let final_x = rotated_x + cx;
-    let final_y = rotated_y + cy;
+    /// the position of the pixel in the space of the sprite
+    fn to_sprite(&self, x: usize, y: usize) -> (f32, f32) {
+        let (x, y) = if self.stretch_x {
+            (
+                (x as f32 / self.stretch) / self.scale,
+                y as f32 / self.scale,
+            )
+        } else {
+            (
+                x as f32 / self.scale,
+                (y as f32 * self.stretch) / self.scale,
+            )
+        };
+        match self.rotation {
+            Some((sin, cos)) => {
+                let translated_x = x - self.origin_x;
+                let translated_y = y - self.origin_y;
+                (
+                    translated_x * cos - translated_y * sin + self.origin_x,
+                    translated_x * sin + translated_y * cos + self.origin_y,
+                )
+            }
+            None => (x, y),
+        }
+    }
+
+    /// the columns and rows of the grid that can be covered by a box of the given half size around the sprite's position
+    /// the bounds are padded by a pixel so rounding never cuts off an edge, the per pixel test decides the rest
+    fn screen_bounds(
+        &self,
+        transform: &Transform,
+        half_width: f64,
+        half_height: f64,
+        grid_columns: Range<usize>,
+        grid_rows: Range<usize>,
+    ) -> (Range<usize>, Range<usize>) {
+        let (sin, cos) = self.rotation.unwrap_or((0.0, 1.0));
+        let (sin, cos) = (sin as f64, cos as f64);
+        let (origin_x, origin_y) = (self.origin_x as f64, self.origin_y as f64);
+        let (scale, stretch) = (self.scale as f64, self.stretch as f64);
+
+        let (mut min_x, mut max_x) = (f64::INFINITY, f64::NEG_INFINITY);
+        let (mut min_y, mut max_y) = (f64::INFINITY, f64::NEG_INFINITY);
+        for (corner_x, corner_y) in [
+            (-half_width, -half_height),
+            (half_width, -half_height),
+            (-half_width, half_height),
+            (half_width, half_height),
+        ] {
+            // undo the rotation, then the scale and stretch
+            let dx = transform.x + corner_x - origin_x;
+            let dy = transform.y + corner_y - origin_y;
+            let x = dx * cos + dy * sin + origin_x;
+            let y = -dx * sin + dy * cos + origin_y;
+            let (x, y) = if self.stretch_x {
+                (x * scale * stretch, y * scale)
+            } else {
+                (x * scale, y * scale / stretch)
+            };
+            (min_x, max_x) = (min_x.min(x), max_x.max(x));
+            (min_y, max_y) = (min_y.min(y), max_y.max(y));
+        }
 
-    (final_x, final_y)
+        if !(min_x.is_finite() && max_x.is_finite() && min_y.is_finite() && max_y.is_finite()) {
+            return (grid_columns, grid_rows);
+        }
+        let clamp = |value: f64, range: &Range<usize>| {
+            (value.max(range.start as f64) as usize).min(range.end)
+        };
+        let columns =
+            clamp(min_x.floor() - 1.0, &grid_columns)..clamp(max_x.ceil() + 2.0, &grid_columns);
+        let rows = clamp(min_y.floor() - 1.0, &grid_rows)..clamp(max_y.ceil() + 2.0, &grid_rows);
+        (columns, rows)
+    }
+}
+
+#[cfg(test)]
+mod shape_renderer_tests {
+    #[test]
+    fn screen_bounds_cover_every_pixel_of_the_sprite() {
+        use super::*;
+
+        for (rotation, scale, stretch) in [(0.0, 1.0, 2.3), (37.0, 0.7, 1.0), (-120.0, 2.5, 0.5)] {
+            let transform = Transform {
+                x: 20.0,
+                y: 14.0,
+                rotation,
+                scale,
+                origin_x: 1.5,
+                origin_y: -2.0,
+                ..Default::default()
+            };
+            let (half_width, half_height) = (6.0, 3.5);
+            for sprite_space in [
+                SpriteSpace::shape(&transform, stretch),
+                SpriteSpace::texture(&transform, stretch),
+            ] {
+                let (columns, rows) =
+                    sprite_space.screen_bounds(&transform, half_width, half_height, 0..100, 0..100);
+                for y in 0..100 {
+                    for x in 0..100 {
+                        let (sprite_x, sprite_y) = sprite_space.to_sprite(x, y);
+                        if (sprite_x as f64 - transform.x).abs() <= half_width
+                            && (sprite_y as f64 - transform.y).abs() <= half_height
+                        {
+                            assert!(columns.contains(&x) && rows.contains(&y));
+                        }
+                    }
+                }
+                assert!(columns.len() < 100 && rows.len() < 100);
+            }
+        }
+    }
+
+    #[test]
+    fn mask_grid_only_covers_the_sprite() {
+        use super::*;
+        use crate::test_helpers::*;
+
+        let rectangle = Rectangle {
+            width: 10.0,
+            height: 10.0,
+            color: RED,
+        };
+        let transform = Transform {
+            x: 25.0,
+            y: 15.0,
+            ..Default::default()
+        };
+        let hole = Circle {
+            radius: 2.0,
+            color: Color {
+                a: 0.0,
+                ..Color::default()
+            },
+        };
+        let mask = Mask::new(hole.into(), Transform::default());
+
+        let mut frame = Frame::new(40, 30, Color::default());
+        let mut mask_grid = FrameBuffer::default();
+        render_rectangle_with_mask(
+            &rectangle,
+            &transform,
+            &mut frame.as_band_mut(0),
+            1.0,
+            &mask,
+            &mut mask_grid,
+        );
+        assert!(mask_grid.width() < 20 && mask_grid.height() < 20);
+        assert_eq!(frame[15][25], Color::default());
+        assert_eq!(frame[11][21], RED);
+    }
 }
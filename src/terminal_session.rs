@@ -0,0 +1,104 @@
+use crossterm::{cursor, style, terminal};
+use std::io::Write;
+use std::sync::{Mutex, MutexGuard, Once, PoisonError};
+
+// the stream of the session that has the terminal, None if there is none, so it is only restored once
+static SESSION_WRITER: Mutex<Option<Box<dyn Write + Send>>> = Mutex::new(None);
+static INSTALL_PANIC_HOOK: Once = Once::new();
+
+/// A guard that switches the terminal to the alternate screen and raw mode and hides the cursor
+/// the terminal is restored when the session is dropped or when the program panics,
+/// so the shell isn't left with a hidden cursor and leftover colors
+/// give it the stream the renderer writes to (stdout for new), so both restore the same terminal
+/// create it before the renderer so the renderer is dropped first
+pub struct TerminalSession {
+    // only constructed through new, try_new, with_writer and try_with_writer
+    _private: (),
+}
+
+impl TerminalSession {
+    /// enters the alternate screen and raw mode on stdout and installs a panic hook that restores the terminal
+    /// panics if stdout isn't a terminal, use try_new to handle the error instead
+    pub fn new() -> TerminalSession {
+        TerminalSession::with_writer(std::io::stdout())
+    }
+
+    /// enters the alternate screen and raw mode on stdout, returns an error if stdout isn't a terminal
+    pub fn try_new() -> std::io::Result<TerminalSession> {
+        TerminalSession::try_with_writer(std::io::stdout())
+    }
+
+    /// like new, but for the stream a renderer made with Renderer::with_backend writes to, for example stderr
+    /// panics if the terminal can't be set up, use try_with_writer to handle the error instead
+    pub fn with_writer(writer: impl Write + Send + 'static) -> TerminalSession {
+        match TerminalSession::try_with_writer(writer) {
+            Ok(session) => session,
+            Err(error) => panic!("failed to set up the terminal: {}", error),
+        }
+    }
+
+    /// like try_new, but the alternate screen is entered and restored on the given stream
+    pub fn try_with_writer(
+        mut writer: impl Write + Send + 'static,
+    ) -> std::io::Result<TerminalSession> {
+        INSTALL_PANIC_HOOK.call_once(|| {
+            let default_hook = std::panic::take_hook();
+            std::panic::set_hook(Box::new(move |panic_info| {
+                // restore first so the panic message is printed to the normal screen
+                restore_terminal();
+                default_hook(panic_info);
+            }));
+        });
+
+        terminal::enable_raw_mode()?;
+        let entered = crossterm::execute!(writer, terminal::EnterAlternateScreen, cursor::Hide);
+        // the session is active from here on, so a failure above still disables raw mode when it is dropped
+        *session_writer() = Some(Box::new(writer));
+        let session = TerminalSession { _private: () };
+        entered?;
+
+        Ok(session)
+    }
+
+    /// returns true while the terminal is in the alternate screen and raw mode
+    pub fn is_active() -> bool {
+        session_writer().is_some()
+    }
+}
+
+impl Default for TerminalSession {
+    fn default() -> Self {
+        TerminalSession::new()
+    }
+}
+
+impl Drop for TerminalSession {
+    fn drop(&mut self) {
+        restore_terminal();
+    }
+}
+
+/// the stream of the active session, a panic while it was locked doesn't keep the terminal from being restored
+fn session_writer() -> MutexGuard<'static, Option<Box<dyn Write + Send>>> {
+    SESSION_WRITER
+        .lock()
+        .unwrap_or_else(PoisonError::into_inner)
+}
+
+/// shows the cursor, resets the colors and leaves the alternate screen and raw mode on the session's stream
+/// does nothing if there is no active session, errors are ignored because this also runs while panicking
+fn restore_terminal() {
+    // the lock is released before writing, so a panic in the stream can't leave it locked for the panic hook
+    let Some(mut writer) = session_writer().take() else {
+        return;
+    };
+    let _ = crossterm::queue!(
+        writer,
+        style::SetAttribute(style::Attribute::Reset),
+        style::ResetColor,
+        cursor::Show,
+        terminal::LeaveAlternateScreen
+    );
+    let _ = writer.flush();
+    let _ = terminal::disable_raw_mode();
+}
@@ -0,0 +1,46 @@
+use crate::*;
+
+pub(crate) const RED: Color = Color {
+    r: 255,
+    g: 0,
+    b: 0,
+    a: 1.0,
+};
+
+pub(crate) const GREEN: Color = Color {
+    r: 0,
+    g: 255,
+    b: 0,
+    a: 1.0,
+};
+
+pub(crate) const BLUE: Color = Color {
+    r: 0,
+    g: 0,
+    b: 255,
+    a: 1.0,
+};
+
+pub(crate) const WHITE: Color = Color {
+    r: 255,
+    g: 255,
+    b: 255,
+    a: 1.0,
+};
+
+pub(crate) const GRAY: Color = Color {
+    r: 200,
+    g: 200,
+    b: 200,
+    a: 1.0,
+};
+
+/// a true color renderer that writes to a MemoryBackend, what it wrote while being set up is already taken
+pub(crate) fn memory_renderer() -> (Renderer, MemoryBackend) {
+    let backend = MemoryBackend::new();
+    let mut renderer = Renderer::with_backend(backend.clone());
+    renderer.set_color_depth(ColorDepth::TrueColor);
+    renderer.handle.flush().expect("memory backend can't fail");
+    backend.take();
+    (renderer, backend)
+}